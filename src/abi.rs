@@ -0,0 +1,24 @@
+use ethereum_types::H160;
+
+/// ABI-encodes a 20-byte address as a 32-byte word.
+pub fn address_word(address: &H160) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[12..].copy_from_slice(address.as_bytes());
+    word
+}
+
+/// ABI-encodes an unsigned integer as a 32-byte word.
+pub fn uint_word(value: usize) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&(value as u64).to_be_bytes());
+    word
+}
+
+/// ABI-encodes the tail of a dynamic `bytes` value: its length followed by
+/// the data, zero-padded to a multiple of 32 bytes.
+pub fn bytes_tail(data: &[u8]) -> Vec<u8> {
+    let mut tail = uint_word(data.len()).to_vec();
+    tail.extend(data);
+    tail.resize(32 + data.len().div_ceil(32) * 32, 0);
+    tail
+}
@@ -0,0 +1,51 @@
+use crate::abi::{address_word, bytes_tail, uint_word};
+use ethereum_types::H160;
+use sha3::{Digest, Keccak256};
+
+/// Selector of `initialize(address)`.
+const INITIALIZE_SELECTOR: [u8; 4] = [0xc4, 0xd6, 0x6d, 0xe8];
+
+/// Init code hash of a SimpleAccountFactory-style smart account:
+/// `keccak256(proxyCreationCode ++ abi.encode(implementation, abi.encodeCall(initialize, (owner))))`.
+///
+/// The factory deploys the account proxy with CREATE2 using the `salt`
+/// passed to `createAccount(owner, salt)` as is, so with this hash the
+/// account address is an ordinary CREATE2 derivation from the factory.
+pub fn simple_account_init_code_hash(proxy_creation_code: &[u8], implementation: &H160, owner: &H160) -> [u8; 32] {
+    let mut initialize_call = INITIALIZE_SELECTOR.to_vec();
+    initialize_call.extend(address_word(owner));
+
+    let mut init_code = proxy_creation_code.to_vec();
+    init_code.extend(address_word(implementation));
+    init_code.extend(uint_word(2 * 32)); // offset of the initialize call data
+    init_code.extend(bytes_tail(&initialize_call));
+
+    Keccak256::digest(init_code).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::Create2Miner;
+    use crate::search::Worker;
+
+    /// `SimpleAccountFactory.getAddress(owner, salt)` with a stand-in proxy
+    /// creation code. No node was reachable to call a deployed factory, so
+    /// the expected values come from a separate Python implementation of the
+    /// ABI encoding, CREATE2 and Keccak-256.
+    #[test]
+    fn simple_account_test_vector() {
+        let init_code_hash = simple_account_init_code_hash(
+            &hex::decode("608060405260405161").unwrap(),
+            &H160::repeat_byte(0x33),
+            &H160::repeat_byte(0x44),
+        );
+        assert_eq!(hex::encode(init_code_hash), "350cc28326a2ab4818361462d0e3e06b6fedc8140a979518b0d4b6252886d605");
+
+        let mut salt = [0u8; 32];
+        salt[31] = 7;
+        let mut miner = Create2Miner::new(&H160::repeat_byte(0x55), salt, &init_code_hash, 1);
+        assert!(miner.next_batch());
+        assert_eq!(miner.addresses()[0], "5afa2cb5ceb211271249a305050518ee74d23e7d".parse().unwrap());
+    }
+}
@@ -0,0 +1,47 @@
+use ethereum_types::H160;
+use sha3::{Digest, Keccak256};
+
+/// Keccak hash of the lowercase hex address, as used by EIP-55.
+///
+/// Hex letter `i` of the checksum address is uppercase iff nibble `i` of this
+/// hash is 8 or more.
+pub fn checksum_hash(address: &H160) -> [u8; 32] {
+    let mut hex = [0u8; 40];
+    hex::encode_to_slice(address.as_bytes(), &mut hex).unwrap();
+    Keccak256::digest(hex).into()
+}
+
+/// Whether hex letter `position` of the address is uppercase in checksum form.
+pub fn is_uppercase(hash: &[u8; 32], position: usize) -> bool {
+    let nibble = if position % 2 == 1 {
+        hash[position / 2] & 0xf
+    } else {
+        hash[position / 2] >> 4
+    };
+    nibble >= 8
+}
+
+/// The 40 hex characters of an address in checksum case, without `0x`.
+pub fn checksum_hex(address: &H160) -> [u8; 40] {
+    checksum_hex_with_hash(address, &checksum_hash(address))
+}
+
+/// [`checksum_hex`] for an address whose [`checksum_hash`] is already known.
+pub fn checksum_hex_with_hash(address: &H160, hash: &[u8; 32]) -> [u8; 40] {
+    let mut hex = [0u8; 40];
+    hex::encode_to_slice(address.as_bytes(), &mut hex).unwrap();
+
+    for (i, c) in hex.iter_mut().enumerate() {
+        if is_uppercase(hash, i) {
+            c.make_ascii_uppercase();
+        }
+    }
+
+    hex
+}
+
+/// Formats an address in EIP-55 mixed-case checksum form.
+pub fn to_checksum_address(address: &H160) -> String {
+    let hex = checksum_hex(address);
+    format!("0x{}", std::str::from_utf8(&hex).unwrap())
+}
@@ -0,0 +1,372 @@
+use crate::search::{KeyWalker, Secret, Worker};
+use ethereum_types::H160;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha3::{Digest, Keccak256};
+use std::ops::RangeInclusive;
+
+/// Keccak-256 hash of the minimal CREATE3 proxy init code
+/// `0x67363d3d37363d34f03d5260086018f3` used by the Solmate and Solady libraries.
+pub const CREATE3_PROXY_INIT_CODE_HASH: &str = "0x21c35dbe1b344a2488cf3321d6ce542f8e9f305544ff09e4993a62319a497c1f";
+
+/// Most nonces a [`CreateMiner`] tries per key. Every key's batch holds an
+/// address per nonce, so wider ranges take memory and time out of all
+/// proportion to the odds they add.
+pub const MAX_NONCES: u64 = 1024;
+
+/// Checks that a [`CreateMiner`] can take `nonces`: not empty and at most
+/// [`MAX_NONCES`] wide.
+pub fn check_nonces(nonces: &RangeInclusive<u64>) -> Result<(), String> {
+    if nonces.start() > nonces.end() {
+        return Err("nonce range must not be empty".to_string());
+    }
+    if nonces.end() - nonces.start() >= MAX_NONCES {
+        return Err(format!("nonce range can span at most {} nonces", MAX_NONCES));
+    }
+    Ok(())
+}
+
+/// Address of a contract deployed with CREATE: `keccak256(rlp([sender, nonce]))[12..]`.
+pub fn create_address(sender: &H160, nonce: u64) -> H160 {
+    // The RLP list is always short: 21 bytes of address and at most 9 of nonce
+    let mut rlp = [0u8; 32];
+    rlp[1] = 0x80 + 20;
+    rlp[2..22].copy_from_slice(sender.as_bytes());
+
+    let nonce_bytes = nonce.to_be_bytes();
+    let significant = &nonce_bytes[nonce.leading_zeros() as usize / 8..];
+    let end = match nonce {
+        0 => {
+            rlp[22] = 0x80;
+            23
+        }
+        1..=0x7f => {
+            rlp[22] = nonce as u8;
+            23
+        }
+        _ => {
+            rlp[22] = 0x80 + significant.len() as u8;
+            rlp[23..23 + significant.len()].copy_from_slice(significant);
+            23 + significant.len()
+        }
+    };
+    rlp[0] = 0xc0 + (end - 1) as u8;
+
+    H160::from_slice(&Keccak256::digest(&rlp[..end])[12..])
+}
+
+/// Mines private keys whose CREATE deployments at the given nonces land on
+/// a vanity address.
+///
+/// Keys come from a [`KeyWalker`] as usual; each key's account address then
+/// gets an RLP encoding and a second Keccak hash per nonce.
+pub struct CreateMiner {
+    walker: KeyWalker,
+    nonces: RangeInclusive<u64>,
+    addresses: Vec<H160>,
+}
+
+impl CreateMiner {
+    /// Fails on the ranges [`check_nonces`] rejects.
+    pub fn new(walker: KeyWalker, nonces: RangeInclusive<u64>) -> Result<Self, String> {
+        check_nonces(&nonces)?;
+        Ok(CreateMiner {
+            walker,
+            nonces,
+            addresses: Vec::new(),
+        })
+    }
+
+    fn nonce_count(&self) -> u64 {
+        self.nonces.end() - self.nonces.start() + 1
+    }
+}
+
+impl Worker for CreateMiner {
+    fn next_batch(&mut self) -> bool {
+        if !self.walker.next_batch() {
+            return false;
+        }
+
+        self.addresses.clear();
+        for sender in self.walker.addresses() {
+            for nonce in self.nonces.clone() {
+                self.addresses.push(create_address(sender, nonce));
+            }
+        }
+
+        true
+    }
+
+    fn addresses(&self) -> &[H160] {
+        &self.addresses
+    }
+
+    fn secret(&self, index: usize) -> Secret {
+        let key = (index as u64 / self.nonce_count()) as usize;
+        Secret::Deployer {
+            private_key: self.walker.secret_key(key),
+            address: self.walker.addresses()[key],
+            nonce: self.nonces.start() + index as u64 % self.nonce_count(),
+        }
+    }
+}
+
+/// Mines CREATE2 salts for a fixed deployer and init code hash.
+///
+/// Each worker starts from a random salt and counts up through its last
+/// 8 bytes, rewriting only those bytes of the hash preimage per attempt. No
+/// elliptic-curve math is involved, just one Keccak hash per candidate.
+pub struct Create2Miner {
+    /// `0xff ++ deployer ++ salt ++ init_code_hash`
+    preimage: [u8; 85],
+    /// Value of the salt's last 8 bytes for the first address of the batch.
+    counter: u64,
+    batch_size: usize,
+    addresses: Vec<H160>,
+}
+
+impl Create2Miner {
+    pub fn random(deployer: &H160, init_code_hash: &[u8; 32], batch_size: usize) -> Self {
+        let mut salt = [0u8; 32];
+        OsRng.fill_bytes(&mut salt);
+        Self::new(deployer, salt, init_code_hash, batch_size)
+    }
+
+    /// Starts counting at `salt`.
+    pub fn new(deployer: &H160, salt: [u8; 32], init_code_hash: &[u8; 32], batch_size: usize) -> Self {
+        let mut preimage = [0u8; 85];
+        preimage[0] = 0xff;
+        preimage[1..21].copy_from_slice(deployer.as_bytes());
+        preimage[21..53].copy_from_slice(&salt);
+        preimage[53..85].copy_from_slice(init_code_hash);
+
+        Create2Miner {
+            preimage,
+            counter: u64::from_be_bytes(salt[24..].try_into().unwrap()),
+            batch_size,
+            addresses: Vec::with_capacity(batch_size),
+        }
+    }
+
+    /// Salt behind `addresses()[index]`.
+    pub fn salt(&self, index: usize) -> [u8; 32] {
+        let mut salt: [u8; 32] = self.preimage[21..53].try_into().unwrap();
+        salt[24..].copy_from_slice(&self.counter.wrapping_add(index as u64).to_be_bytes());
+        salt
+    }
+}
+
+impl Worker for Create2Miner {
+    fn next_batch(&mut self) -> bool {
+        self.counter = self.counter.wrapping_add(self.addresses.len() as u64);
+        self.addresses.clear();
+
+        for i in 0..self.batch_size {
+            let counter = self.counter.wrapping_add(i as u64);
+            self.preimage[45..53].copy_from_slice(&counter.to_be_bytes());
+            self.addresses.push(H160::from_slice(&Keccak256::digest(self.preimage)[12..]));
+        }
+
+        true
+    }
+
+    fn addresses(&self) -> &[H160] {
+        &self.addresses
+    }
+
+    fn secret(&self, index: usize) -> Secret {
+        Secret::Salt(self.salt(index))
+    }
+}
+
+/// Mines CREATE3 salts for a fixed factory and proxy init code hash.
+///
+/// The factory deploys a proxy with CREATE2 and the proxy then deploys the
+/// contract with CREATE as its first transaction (nonce 1), so each salt from
+/// a [`Create2Miner`] takes one more RLP encoding and Keccak hash.
+pub struct Create3Miner {
+    proxies: Create2Miner,
+    addresses: Vec<H160>,
+}
+
+impl Create3Miner {
+    pub fn new(proxies: Create2Miner) -> Self {
+        Create3Miner {
+            proxies,
+            addresses: Vec::new(),
+        }
+    }
+}
+
+impl Worker for Create3Miner {
+    fn next_batch(&mut self) -> bool {
+        if !self.proxies.next_batch() {
+            return false;
+        }
+
+        self.addresses.clear();
+        self.addresses
+            .extend(self.proxies.addresses().iter().map(|proxy| create_address(proxy, 1)));
+
+        true
+    }
+
+    fn addresses(&self) -> &[H160] {
+        &self.addresses
+    }
+
+    fn secret(&self, index: usize) -> Secret {
+        self.proxies.secret(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::public_key_address;
+    use secp256k1::Secp256k1;
+
+    /// The sender and first two nonces are the example everyone quotes from
+    /// Ethereum StackExchange; the addresses at the boundaries of the nonce's
+    /// RLP encoding come from a separate Python implementation of RLP and
+    /// Keccak-256, which reproduces that example.
+    #[test]
+    fn create_test_vectors() {
+        let sender: H160 = "6ac7ea33f8831ea9dcc53393aaa88b25a785dbf0".parse().unwrap();
+        let vectors = [
+            (0, "cd234a471b72ba2f1ccf0a70fcaba648a5eecd8d"),
+            (1, "343c43a37d37dff08ae8c4a11544c718abb4fcf8"),
+            (0x7f, "06d9a77f5e4b311bae8d559db9cdb4df94104aa0"),
+            (0x80, "08e190dcb7b73f5fcdabb43e102215c83659a76d"),
+            (0xff, "3ef7c1a519e4b4431e317d7839340e3139b03c65"),
+            (0x100, "3837c1ae70354f670550c746580199ac6a73cb0a"),
+            (u64::MAX, "9bc924993b60399df164c3763a964301d3db95ca"),
+        ];
+        for (nonce, expected) in vectors {
+            assert_eq!(create_address(&sender, nonce), expected.parse().unwrap(), "nonce {}", nonce);
+        }
+    }
+
+    #[test]
+    fn create_miner_checks_nonces() {
+        let walker = || KeyWalker::random(&Secp256k1::new(), 1);
+        assert!(CreateMiner::new(walker(), 5..=5).is_ok());
+        assert!(CreateMiner::new(walker(), 0..=MAX_NONCES - 1).is_ok());
+        assert!(CreateMiner::new(walker(), 0..=MAX_NONCES).is_err());
+        assert!(CreateMiner::new(walker(), 0..=u64::MAX).is_err());
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 5..=4;
+        assert!(CreateMiner::new(walker(), empty).is_err());
+    }
+
+    /// Every address of a few batches against the key and nonce reported for it.
+    #[test]
+    fn create_secret_reproduces_addresses() {
+        let secp = Secp256k1::new();
+        let mut miner = CreateMiner::new(KeyWalker::random(&secp, 3), 0x7e..=0x81).unwrap();
+        for _ in 0..2 {
+            assert!(miner.next_batch());
+            assert_eq!(miner.addresses().len(), 12);
+            for (i, address) in miner.addresses().iter().enumerate() {
+                let Secret::Deployer { private_key, address: sender, nonce } = miner.secret(i) else {
+                    panic!("CreateMiner reports deployers");
+                };
+                assert_eq!(public_key_address(&private_key.public_key(&secp)), sender);
+                assert_eq!(*address, create_address(&sender, nonce), "address {} of the batch", i);
+            }
+        }
+    }
+
+    fn create2_address(deployer: &str, salt: &str, init_code: &str) -> H160 {
+        let salt = hex::decode(salt).unwrap().try_into().unwrap();
+        let init_code_hash = Keccak256::digest(hex::decode(init_code).unwrap()).into();
+        let mut miner = Create2Miner::new(&deployer.parse().unwrap(), salt, &init_code_hash, 1);
+        assert!(miner.next_batch());
+        assert_eq!(miner.salt(0), salt);
+        miner.addresses()[0]
+    }
+
+    /// The examples of EIP-1014.
+    #[test]
+    fn create2_test_vectors() {
+        let zero_salt = "0000000000000000000000000000000000000000000000000000000000000000";
+        let cafebabe = "00000000000000000000000000000000000000000000000000000000cafebabe";
+        let vectors = [
+            ("0000000000000000000000000000000000000000", zero_salt, "00", "4d1a2e2bb4f88f0250f26ffff098b0b30b26bf38"),
+            ("deadbeef00000000000000000000000000000000", zero_salt, "00", "b928f69bb1d91cd65274e3c79d8986362984fda3"),
+            (
+                "deadbeef00000000000000000000000000000000",
+                "000000000000000000000000feed000000000000000000000000000000000000",
+                "00",
+                "d04116cdd17bebe565eb2422f2497e06cc1c9833",
+            ),
+            ("0000000000000000000000000000000000000000", zero_salt, "deadbeef", "70f2b2914a2a4b783faefb75f459a580616fcb5e"),
+            ("00000000000000000000000000000000deadbeef", cafebabe, "deadbeef", "60f3f640a8508fc6a86d45df051962668e1e8ac7"),
+            (
+                "00000000000000000000000000000000deadbeef",
+                cafebabe,
+                "deadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef",
+                "1d8bfdc5d46dc4f61d6b6115972536ebe6a8854c",
+            ),
+            ("0000000000000000000000000000000000000000", zero_salt, "", "e33c0c7f7df4809055c3eba6c09cfe4baf1bd9e0"),
+        ];
+        for (deployer, salt, init_code, expected) in vectors {
+            assert_eq!(create2_address(deployer, salt, init_code), expected.parse().unwrap(), "{} {}", deployer, salt);
+        }
+    }
+
+    #[test]
+    fn create3_proxy_init_code_hash() {
+        let hash = Keccak256::digest(hex::decode("67363d3d37363d34f03d5260086018f3").unwrap());
+        assert_eq!(format!("0x{}", hex::encode(hash)), CREATE3_PROXY_INIT_CODE_HASH);
+    }
+
+    /// Solmate's `CREATE3.getDeployed(salt, creator)`, computed by a separate
+    /// Python implementation of CREATE2, RLP and Keccak-256 as no node was
+    /// reachable to call the library.
+    #[test]
+    fn create3_test_vectors() {
+        let proxy_init_code_hash = hex::decode(&CREATE3_PROXY_INIT_CODE_HASH[2..]).unwrap().try_into().unwrap();
+        let vectors = [
+            (0, "abf537c0ec0795459d4fe63b437e1228344068b5", "b0e8ecc14e80b558dd252c4cc43b17129ac19b46"),
+            (1, "331ad9b0fcd7ae93353595227c6521c6c19e6742", "b3c6f3db6eae4a041880aac733b0e222cc60bc91"),
+        ];
+        for (salt, proxy, expected) in vectors {
+            let mut salt_bytes = [0u8; 32];
+            salt_bytes[31] = salt;
+            let proxies = Create2Miner::new(&H160::repeat_byte(0x42), salt_bytes, &proxy_init_code_hash, 1);
+            let mut miner = Create3Miner::new(proxies);
+            assert!(miner.next_batch());
+            assert_eq!(miner.proxies.addresses()[0], proxy.parse().unwrap(), "salt {}", salt);
+            assert_eq!(miner.addresses()[0], expected.parse().unwrap(), "salt {}", salt);
+            assert!(matches!(miner.secret(0), Secret::Salt(reported) if reported == salt_bytes));
+        }
+    }
+
+    /// Every address of a few batches against the salt reported for it,
+    /// starting just below a 2^64 boundary of the counter.
+    #[test]
+    fn create2_salt_reproduces_addresses() {
+        let deployer = H160::repeat_byte(0x42);
+        let init_code_hash = [0x17; 32];
+        let mut salt = [0xab; 32];
+        salt[24..].copy_from_slice(&(u64::MAX - 5).to_be_bytes());
+
+        for batch_size in [1, 4, 7] {
+            let mut miner = Create2Miner::new(&deployer, salt, &init_code_hash, batch_size);
+            for _ in 0..3 {
+                assert!(miner.next_batch());
+                for (i, address) in miner.addresses().iter().enumerate() {
+                    let hash = Keccak256::new()
+                        .chain_update([0xff])
+                        .chain_update(deployer)
+                        .chain_update(miner.salt(i))
+                        .chain_update(init_code_hash)
+                        .finalize();
+                    assert_eq!(*address, H160::from_slice(&hash[12..]), "address {} of the batch", i);
+                }
+            }
+        }
+    }
+}
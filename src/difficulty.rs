@@ -0,0 +1,85 @@
+/// How hard a search is, from the probability that a single candidate
+/// matches (see [`Matcher::probability`](crate::Matcher::probability)).
+///
+/// Candidates are treated as independent draws, so the number of hits after
+/// `n` attempts is binomial; it is approximated by a Poisson distribution,
+/// which is exact in the limit of the tiny probabilities that make a search
+/// worth estimating.
+#[derive(Clone, Copy, Debug)]
+pub struct Difficulty {
+    pub probability: f64,
+}
+
+impl Difficulty {
+    pub fn new(probability: f64) -> Self {
+        Difficulty { probability }
+    }
+
+    /// Mean number of candidates until `hits` matches.
+    pub fn expected_attempts(&self, hits: usize) -> f64 {
+        hits as f64 / self.probability
+    }
+
+    /// Number of candidates after which `hits` matches have been found with
+    /// probability `confidence`, e.g. 0.9 for the 90th percentile.
+    pub fn attempts_for(&self, hits: usize, confidence: f64) -> f64 {
+        if hits == 0 {
+            return 0.0;
+        }
+        // -ln(1 - p) rather than p makes a single hit follow the geometric
+        // distribution exactly, even for large p
+        let rate = -(-self.probability).ln_1p();
+        (poisson_quantile(hits, confidence) / rate).max(hits as f64)
+    }
+
+    /// Seconds until `hits` matches with probability `confidence`, at `rate`
+    /// candidates per second. Infinite if the rate is 0 or nothing matches.
+    pub fn eta(&self, hits: usize, confidence: f64, rate: f64) -> f64 {
+        if hits == 0 {
+            return 0.0;
+        }
+        if rate <= 0.0 {
+            return f64::INFINITY;
+        }
+        self.attempts_for(hits, confidence) / rate
+    }
+}
+
+/// The mean λ at which a Poisson variable reaches `hits` with probability
+/// `confidence`.
+fn poisson_quantile(hits: usize, confidence: f64) -> f64 {
+    let target = 1.0 - confidence;
+    if hits == 1 {
+        return -target.ln();
+    }
+
+    // P(X < hits) falls as λ grows, so bracket the solution and bisect
+    let (mut low, mut high) = (0.0, hits as f64);
+    while poisson_below(hits, high) > target {
+        low = high;
+        high *= 2.0;
+    }
+    for _ in 0..100 {
+        let middle = (low + high) / 2.0;
+        if poisson_below(hits, middle) > target {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    high
+}
+
+/// P(X < hits) for X ~ Poisson(λ), summed in log space so that large λ
+/// doesn't underflow.
+fn poisson_below(hits: usize, lambda: f64) -> f64 {
+    let mut log_term = -lambda;
+    let mut sum = 0.0;
+    for i in 0..hits {
+        if i > 0 {
+            log_term += lambda.ln() - (i as f64).ln();
+        }
+        sum += log_term.exp();
+    }
+    sum
+}
@@ -0,0 +1,322 @@
+use crate::search::{public_key_address, Secret, Worker};
+use ethereum_types::H160;
+use hmac::{Hmac, Mac};
+use secp256k1::{PublicKey, Scalar, Secp256k1, SecretKey};
+use sha2::Sha512;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use zeroize::{Zeroize, Zeroizing};
+
+/// Child indexes at or above this are hardened.
+pub const HARDENED: u32 = 1 << 31;
+
+/// The usual Ethereum account path, `m/44'/60'/0'/0/0`.
+pub const DEFAULT_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// Parses a BIP-32 derivation path such as `m/44'/60'/0'/0/0` into child
+/// indexes. Hardened indexes may be marked with `'` or `h`.
+pub fn parse_path(input: &str) -> Result<Vec<u32>, String> {
+    let mut components = input.trim().split('/');
+    if components.next() != Some("m") {
+        return Err("derivation path must start with m/".to_string());
+    }
+
+    components
+        .map(|component| {
+            let (number, hardened) = match component.strip_suffix(['\'', 'h']) {
+                Some(number) => (number, true),
+                None => (component, false),
+            };
+            let index: u32 = number
+                .parse()
+                .ok()
+                .filter(|&index| index < HARDENED)
+                .ok_or_else(|| format!("invalid path component '{}'", component))?;
+            Ok(if hardened { index | HARDENED } else { index })
+        })
+        .collect()
+}
+
+/// Version bytes of extended private keys (`xprv`), which we refuse to take.
+const XPRV_VERSION: [u8; 4] = [0x04, 0x88, 0xad, 0xe4];
+
+/// HMAC-SHA512 as used throughout BIP-32: the left half is key material, the
+/// right half the chain code.
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> Zeroizing<[u8; 64]> {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).unwrap();
+    for part in data {
+        mac.update(part);
+    }
+    let mut output = mac.finalize().into_bytes();
+    let result = Zeroizing::new(output.into());
+    output.zeroize();
+    result
+}
+
+/// A BIP-32 extended private key.
+pub struct ExtendedPrivateKey {
+    pub key: SecretKey,
+    pub chain_code: [u8; 32],
+}
+
+impl ExtendedPrivateKey {
+    /// Master key of a BIP-39 seed. Returns `None` for the (negligibly rare)
+    /// seeds whose master key is invalid.
+    pub fn master(seed: &[u8]) -> Option<Self> {
+        let output = hmac_sha512(b"Bitcoin seed", &[seed]);
+        Some(ExtendedPrivateKey {
+            key: SecretKey::from_slice(&output[..32]).ok()?,
+            chain_code: output[32..].try_into().unwrap(),
+        })
+    }
+
+    /// Child key at `index`. Returns `None` if the index yields an invalid
+    /// key, in which case BIP-32 says to move on to the next index.
+    pub fn child(&self, secp: &Secp256k1<secp256k1::All>, index: u32) -> Option<Self> {
+        let index_bytes = index.to_be_bytes();
+        let output = if index >= HARDENED {
+            let key = Zeroizing::new(self.key.secret_bytes());
+            hmac_sha512(&self.chain_code, &[&[0], &*key, &index_bytes])
+        } else {
+            let public_key = PublicKey::from_secret_key(secp, &self.key).serialize();
+            hmac_sha512(&self.chain_code, &[&public_key, &index_bytes])
+        };
+
+        let tweak = Scalar::from_be_bytes(output[..32].try_into().unwrap()).ok()?;
+        Some(ExtendedPrivateKey {
+            key: self.key.add_tweak(&tweak).ok()?,
+            chain_code: output[32..].try_into().unwrap(),
+        })
+    }
+
+    /// Descendant key along `path`, as returned by [`parse_path`].
+    pub fn derive(&self, secp: &Secp256k1<secp256k1::All>, path: &[u32]) -> Option<Self> {
+        path.iter().try_fold(
+            ExtendedPrivateKey {
+                key: self.key,
+                chain_code: self.chain_code,
+            },
+            |key, &index| key.child(secp, index),
+        )
+    }
+
+    /// The matching extended public key.
+    pub fn public(&self, secp: &Secp256k1<secp256k1::All>) -> ExtendedPublicKey {
+        ExtendedPublicKey {
+            key: PublicKey::from_secret_key(secp, &self.key),
+            chain_code: self.chain_code,
+        }
+    }
+}
+
+impl Drop for ExtendedPrivateKey {
+    fn drop(&mut self) {
+        self.key.non_secure_erase();
+        self.chain_code.zeroize();
+    }
+}
+
+/// A BIP-32 extended public key, which can only derive non-hardened children.
+#[derive(Clone, Debug)]
+pub struct ExtendedPublicKey {
+    pub key: PublicKey,
+    pub chain_code: [u8; 32],
+}
+
+impl ExtendedPublicKey {
+    /// Parses a Base58Check serialized extended public key (`xpub...`).
+    pub fn from_base58(input: &str) -> Result<Self, String> {
+        let bytes = bs58::decode(input.trim())
+            .with_check(None)
+            .into_vec()
+            .map_err(|err| format!("invalid extended public key: {}", err))?;
+        if bytes.len() != 78 {
+            return Err("extended key must be 78 bytes long".to_string());
+        }
+        if bytes[..4] == XPRV_VERSION {
+            return Err("expected an extended public key, not a private one".to_string());
+        }
+
+        Ok(ExtendedPublicKey {
+            chain_code: bytes[13..45].try_into().unwrap(),
+            key: PublicKey::from_slice(&bytes[45..78])
+                .map_err(|_| "extended public key contains an invalid point".to_string())?,
+        })
+    }
+}
+
+/// Scans the non-hardened children of an extended public key, for finding a
+/// vanity address among the accounts of an existing wallet.
+///
+/// Only public derivation is used, so no private key is ever needed. The
+/// workers share one counter and claim the next `batch_size` indexes from it
+/// at a time.
+pub struct ChildScanner {
+    secp: Secp256k1<secp256k1::All>,
+    parent: PublicKey,
+    /// HMAC keyed with the parent chain code, already fed the parent key.
+    mac: Hmac<Sha512>,
+    next_index: Arc<AtomicU64>,
+    batch_size: usize,
+    indexes: Vec<u32>,
+    addresses: Vec<H160>,
+}
+
+impl ChildScanner {
+    pub fn new(parent: &ExtendedPublicKey, next_index: Arc<AtomicU64>, batch_size: usize) -> Self {
+        let mut mac = Hmac::<Sha512>::new_from_slice(&parent.chain_code).unwrap();
+        mac.update(&parent.key.serialize());
+
+        ChildScanner {
+            secp: Secp256k1::new(),
+            parent: parent.key,
+            mac,
+            next_index,
+            batch_size,
+            indexes: Vec::with_capacity(batch_size),
+            addresses: Vec::with_capacity(batch_size),
+        }
+    }
+}
+
+impl Worker for ChildScanner {
+    /// Derives the next batch of children. Indexes that BIP-32 declares
+    /// invalid are skipped, and the batch comes back empty once all
+    /// non-hardened indexes have been claimed.
+    fn next_batch(&mut self) -> bool {
+        let start = self.next_index.fetch_add(self.batch_size as u64, Ordering::Relaxed);
+        let end = (start + self.batch_size as u64).min(HARDENED as u64);
+
+        self.indexes.clear();
+        self.addresses.clear();
+        for index in start..end {
+            let mut mac = self.mac.clone();
+            mac.update(&(index as u32).to_be_bytes());
+            let output = mac.finalize().into_bytes();
+
+            let Ok(tweak) = Scalar::from_be_bytes(output[..32].try_into().unwrap()) else {
+                continue;
+            };
+            let Ok(child) = self.parent.add_exp_tweak(&self.secp, &tweak) else {
+                continue;
+            };
+            self.indexes.push(index as u32);
+            self.addresses.push(public_key_address(&child));
+        }
+
+        true
+    }
+
+    fn addresses(&self) -> &[H160] {
+        &self.addresses
+    }
+
+    fn secret(&self, index: usize) -> Secret {
+        Secret::ChildIndex(self.indexes[index])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vector_1_master() -> ExtendedPrivateKey {
+        ExtendedPrivateKey::master(&hex::decode("000102030405060708090a0b0c0d0e0f").unwrap()).unwrap()
+    }
+
+    /// Test vector 1 of BIP-32: private key and chain code at each step.
+    #[test]
+    fn bip32_test_vector_1() {
+        let secp = Secp256k1::new();
+        let master = vector_1_master();
+        let vectors = [
+            (
+                "m",
+                "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+                "873dff81c02f525623fd1fe5167eac3a55a049de3d314bb42ee227ffed37d508",
+            ),
+            (
+                "m/0'",
+                "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+                "47fdacbd0f1097043b78c63c20c34ef4ed9a111d980047ad16282c7ae6236141",
+            ),
+            (
+                "m/0'/1",
+                "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+                "2a7857631386ba23dacac34180dd1983734e444fdbf774041578e9b6adb37c19",
+            ),
+            (
+                "m/0'/1/2'",
+                "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+                "04466b9cc8e161e966409ca52986c584f07e9dc81f735db683c3ff6ec7b1503f",
+            ),
+            (
+                "m/0'/1/2'/2",
+                "0f479245fb19a38a1954c5c7c0ebab2f9bdfd96a17563ef28a6a4b1a2a764ef4",
+                "cfb71883f01676f587d023cc53a35bc7f88f724b1f8c2892ac1275ac822a3edd",
+            ),
+            (
+                "m/0'/1/2'/2/1000000000",
+                "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8",
+                "c783e67b921d2beb8f6b389cc646d7263b4145701dadd2161548a8b078e65e9e",
+            ),
+        ];
+        for (path, key, chain_code) in vectors {
+            let derived = master.derive(&secp, &parse_path(path).unwrap()).unwrap();
+            assert_eq!(hex::encode(derived.key.secret_bytes()), key, "{}", path);
+            assert_eq!(hex::encode(derived.chain_code), chain_code, "{}", path);
+        }
+    }
+
+    #[test]
+    fn xpub_matches_private_derivation() {
+        let secp = Secp256k1::new();
+        let parent = vector_1_master().derive(&secp, &parse_path("m/0'/1").unwrap()).unwrap();
+        let xpub = ExtendedPublicKey::from_base58(
+            "xpub6ASuArnXKPbfEwhqN6e3mwBcDTgzisQN1wXN9BJcM47sSikHjJf3UFHKkNAWbWMiGj7Wf5uMash7SyYq527Hqck2AxYysAA7xmALppuCkwQ",
+        )
+        .unwrap();
+        assert_eq!(xpub.key, parent.public(&secp).key);
+        assert_eq!(xpub.chain_code, parent.chain_code);
+
+        let xprv = "xprv9wTYmMFdV23N2TdNG573QoEsfRrWKQgWeibmLntzniatZvR9BmLnvSxqu53Kw1UmYPxLgboyZQaXwTCg8MSY3H2EU4pWcQDnRnrVA1xe8fs";
+        assert!(ExtendedPublicKey::from_base58(xprv).is_err());
+    }
+
+    /// Every child the scanner derives publicly against the same child
+    /// derived from the private key, over several batches.
+    #[test]
+    fn child_scanner_matches_private_derivation() {
+        let secp = Secp256k1::new();
+        let parent = vector_1_master().derive(&secp, &parse_path("m/44'/60'/0'/0").unwrap()).unwrap();
+        let next_index = Arc::new(AtomicU64::new(5));
+        let mut scanner = ChildScanner::new(&parent.public(&secp), next_index.clone(), 4);
+
+        let mut expected_index = 5;
+        for _ in 0..3 {
+            assert!(scanner.next_batch());
+            assert_eq!(scanner.addresses().len(), 4);
+            for (i, address) in scanner.addresses().iter().enumerate() {
+                let Secret::ChildIndex(index) = scanner.secret(i) else {
+                    panic!("ChildScanner reports child indexes");
+                };
+                assert_eq!(index, expected_index);
+                let child = parent.child(&secp, index).unwrap();
+                assert_eq!(*address, public_key_address(&PublicKey::from_secret_key(&secp, &child.key)));
+                expected_index += 1;
+            }
+        }
+        assert_eq!(next_index.load(Ordering::Relaxed), 17);
+    }
+
+    #[test]
+    fn child_scanner_stops_at_hardened_indexes() {
+        let secp = Secp256k1::new();
+        let parent = vector_1_master().public(&secp);
+        let mut scanner = ChildScanner::new(&parent, Arc::new(AtomicU64::new(HARDENED as u64 - 2)), 4);
+        assert!(scanner.next_batch());
+        assert_eq!(scanner.addresses().len(), 2);
+        assert!(scanner.next_batch());
+        assert!(scanner.addresses().is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+use crate::matcher::Pattern;
+
+/// Uniswap v4 hook permissions and the address bit each one is encoded in,
+/// as defined in v4-core's `Hooks.sol`.
+pub const HOOK_FLAGS: [(&str, u16); 14] = [
+    ("beforeInitialize", 1 << 13),
+    ("afterInitialize", 1 << 12),
+    ("beforeAddLiquidity", 1 << 11),
+    ("afterAddLiquidity", 1 << 10),
+    ("beforeRemoveLiquidity", 1 << 9),
+    ("afterRemoveLiquidity", 1 << 8),
+    ("beforeSwap", 1 << 7),
+    ("afterSwap", 1 << 6),
+    ("beforeDonate", 1 << 5),
+    ("afterDonate", 1 << 4),
+    ("beforeSwapReturnDelta", 1 << 3),
+    ("afterSwapReturnDelta", 1 << 2),
+    ("afterAddLiquidityReturnDelta", 1 << 1),
+    ("afterRemoveLiquidityReturnDelta", 1 << 0),
+];
+
+/// All bits that encode hook permissions.
+const ALL_HOOK_FLAGS: u16 = (1 << 14) - 1;
+
+/// Parses a comma-separated list of hook permissions such as
+/// `beforeSwap,afterAddLiquidity`. Names are case-insensitive and may also be
+/// written in kebab or snake case.
+pub fn parse_hook_flags(input: &str) -> Result<u16, String> {
+    let normalize = |name: &str| name.replace(['-', '_'], "").to_lowercase();
+
+    input
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .try_fold(0, |flags, name| {
+            HOOK_FLAGS
+                .iter()
+                .find(|(flag, _)| normalize(flag) == normalize(name))
+                .map(|(_, bit)| flags | bit)
+                .ok_or_else(|| format!("unknown hook flag '{}'", name))
+        })
+}
+
+/// Names of the permissions set in `flags`.
+pub fn hook_flag_names(flags: u16) -> Vec<&'static str> {
+    HOOK_FLAGS
+        .iter()
+        .filter(|(_, bit)| flags & bit != 0)
+        .map(|(name, _)| *name)
+        .collect()
+}
+
+/// Requires the low 14 bits of the address to be exactly `flags`: every
+/// requested permission bit set and every other permission bit cleared.
+pub fn hook_pattern(flags: u16) -> Pattern {
+    let mut mask = [0u8; 20];
+    let mut value = [0u8; 20];
+    mask[18..].copy_from_slice(&ALL_HOOK_FLAGS.to_be_bytes());
+    value[18..].copy_from_slice(&flags.to_be_bytes());
+    Pattern::from_bits(mask, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::{Candidate, Matcher};
+    use ethereum_types::H160;
+
+    #[test]
+    fn parse_hook_flags_accepts_any_case() {
+        assert_eq!(parse_hook_flags("beforeSwap,afterAddLiquidity"), Ok((1 << 7) | (1 << 10)));
+        assert_eq!(parse_hook_flags("before-swap, after_add_liquidity"), Ok((1 << 7) | (1 << 10)));
+        assert_eq!(parse_hook_flags("BEFORE_SWAP"), Ok(1 << 7));
+        assert_eq!(parse_hook_flags("afterRemoveLiquidityReturnDelta"), Ok(1));
+        assert_eq!(parse_hook_flags(""), Ok(0));
+        assert_eq!(parse_hook_flags("beforeSwap,beforeSwap"), Ok(1 << 7));
+    }
+
+    #[test]
+    fn parse_hook_flags_rejects_unknown_names() {
+        assert_eq!(parse_hook_flags("beforeSwap,afterFlash"), Err("unknown hook flag 'afterFlash'".to_string()));
+        assert!(parse_hook_flags("swap").is_err());
+    }
+
+    #[test]
+    fn hook_flag_names_round_trip() {
+        let flags = parse_hook_flags("beforeInitialize,afterSwap").unwrap();
+        assert_eq!(hook_flag_names(flags), ["beforeInitialize", "afterSwap"]);
+        assert_eq!(parse_hook_flags(&hook_flag_names(ALL_HOOK_FLAGS).join(",")), Ok(ALL_HOOK_FLAGS));
+    }
+
+    /// An address ending in the given 16 bits, with everything above random.
+    fn address_ending(low_bits: u16) -> H160 {
+        let mut address = H160::random();
+        address.0[18..].copy_from_slice(&low_bits.to_be_bytes());
+        address
+    }
+
+    #[test]
+    fn hook_pattern_requires_exactly_the_flags() {
+        let flags = parse_hook_flags("beforeSwap,afterAddLiquidity").unwrap();
+        let pattern = hook_pattern(flags);
+        let matches = |address: H160| pattern.matches(&Candidate::new(&address));
+
+        assert!(matches(address_ending(flags)));
+        // The two bits above the permissions are free
+        assert!(matches(address_ending(flags | 0xc000)));
+        for (name, bit) in HOOK_FLAGS {
+            assert!(!matches(address_ending(flags ^ bit)), "{} flipped", name);
+        }
+        assert!(hook_pattern(0).matches(&Candidate::new(&address_ending(0))));
+        assert_eq!(pattern.probability(), 2.0f64.powi(-14));
+    }
+}
@@ -0,0 +1,258 @@
+use aes::Aes128;
+use clap::ValueEnum;
+use ctr::cipher::{KeyIvInit, StreamCipher};
+use ethereum_types::H160;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use secp256k1::SecretKey;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
+
+/// Key derivation function protecting a keystore file.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Kdf {
+    /// scrypt with n = 2^18, r = 8, p = 1, as geth writes by default
+    Scrypt,
+    /// PBKDF2-HMAC-SHA256 with 262144 rounds
+    Pbkdf2,
+}
+
+impl std::fmt::Display for Kdf {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+const SCRYPT_LOG_N: u8 = 18;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const PBKDF2_ROUNDS: u32 = 262144;
+
+/// Writes private keys into a directory as Web3 Secret Storage (keystore V3)
+/// files, named and laid out like geth's so they import into geth, MetaMask
+/// and other wallets.
+pub struct Keystore {
+    dir: PathBuf,
+    kdf: Kdf,
+    passphrase: Zeroizing<String>,
+}
+
+impl Keystore {
+    /// Creates `dir` if needed and checks that files can be written to it,
+    /// so that a problem shows up before the search rather than on a hit.
+    pub fn new(dir: &Path, kdf: Kdf, passphrase: Zeroizing<String>) -> Result<Self, String> {
+        std::fs::create_dir_all(dir).map_err(|err| format!("cannot create {}: {}", dir.display(), err))?;
+
+        let mut id = [0u8; 16];
+        OsRng.fill_bytes(&mut id);
+        let probe = dir.join(format!(".probe-{}", hex::encode(id)));
+        OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&probe)
+            .and_then(|_| std::fs::remove_file(&probe))
+            .map_err(|err| format!("cannot write to {}: {}", dir.display(), err))?;
+
+        Ok(Keystore {
+            dir: dir.to_path_buf(),
+            kdf,
+            passphrase,
+        })
+    }
+
+    /// Encrypts the key and writes it to a new file, returning its path.
+    pub fn write(&self, private_key: &SecretKey, address: &H160) -> Result<PathBuf, String> {
+        let path = self
+            .dir
+            .join(format!("UTC--{}--{:x}", utc_timestamp(SystemTime::now()), address));
+        let json = encrypt(private_key, address, &self.passphrase, self.kdf);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        options
+            .open(&path)
+            .and_then(|mut file| file.write_all(json.as_bytes()))
+            .map_err(|err| format!("cannot write {}: {}", path.display(), err))?;
+
+        Ok(path)
+    }
+}
+
+/// Encrypts a private key into keystore V3 JSON.
+///
+/// The derived key's first half is the AES-128-CTR key; its second half,
+/// hashed together with the ciphertext, is the MAC that wallets check the
+/// passphrase against.
+pub fn encrypt(private_key: &SecretKey, address: &H160, passphrase: &str, kdf: Kdf) -> String {
+    let mut salt = [0u8; 32];
+    let mut iv = [0u8; 16];
+    let mut id = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut iv);
+    OsRng.fill_bytes(&mut id);
+    encrypt_with(private_key, address, passphrase, kdf, &salt, &iv, id)
+}
+
+/// [`encrypt`] with the given KDF salt, cipher IV and id bytes.
+fn encrypt_with(
+    private_key: &SecretKey,
+    address: &H160,
+    passphrase: &str,
+    kdf: Kdf,
+    salt: &[u8; 32],
+    iv: &[u8; 16],
+    id: [u8; 16],
+) -> String {
+    let mut derived_key = Zeroizing::new([0u8; 32]);
+    let kdf_json = match kdf {
+        Kdf::Scrypt => {
+            let params = scrypt::Params::new(SCRYPT_LOG_N, SCRYPT_R, SCRYPT_P, 32).unwrap();
+            scrypt::scrypt(passphrase.as_bytes(), salt, &params, &mut *derived_key).unwrap();
+            format!(
+                r#""kdf":"scrypt","kdfparams":{{"dklen":32,"n":{},"p":{},"r":{},"salt":"{}"}}"#,
+                1u32 << SCRYPT_LOG_N,
+                SCRYPT_P,
+                SCRYPT_R,
+                hex::encode(salt)
+            )
+        }
+        Kdf::Pbkdf2 => {
+            pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut *derived_key);
+            format!(
+                r#""kdf":"pbkdf2","kdfparams":{{"c":{},"dklen":32,"prf":"hmac-sha256","salt":"{}"}}"#,
+                PBKDF2_ROUNDS,
+                hex::encode(salt)
+            )
+        }
+    };
+
+    let mut ciphertext = private_key.secret_bytes();
+    ctr::Ctr128BE::<Aes128>::new(derived_key[..16].into(), iv.into()).apply_keystream(&mut ciphertext);
+
+    let mut mac = Keccak256::new();
+    mac.update(&derived_key[16..]);
+    mac.update(ciphertext);
+
+    format!(
+        r#"{{"address":"{:x}","crypto":{{"cipher":"aes-128-ctr","ciphertext":"{}","cipherparams":{{"iv":"{}"}},{},"mac":"{}"}},"id":"{}","version":3}}"#,
+        address,
+        hex::encode(ciphertext),
+        hex::encode(iv),
+        kdf_json,
+        hex::encode(mac.finalize()),
+        uuid_v4(id)
+    )
+}
+
+/// Formats random bytes as a version 4 UUID.
+fn uuid_v4(mut bytes: [u8; 16]) -> String {
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = hex::encode(bytes);
+    format!("{}-{}-{}-{}-{}", &hex[..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..])
+}
+
+/// Timestamp in the form geth puts in keystore file names,
+/// e.g. `2024-01-31T12-00-00.000000000Z`.
+fn utc_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let seconds = since_epoch.as_secs();
+    let (days, seconds_of_day) = (seconds / 86400, seconds % 86400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}-{:02}-{:02}.{:09}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_nanos()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The test vectors of the Web3 Secret Storage Definition, which geth
+    /// checks its own implementation against.
+    fn check_vector(kdf: Kdf, salt: &str, iv: &str, ciphertext: &str, mac: &str) {
+        let private_key =
+            SecretKey::from_slice(&hex::decode("7a28b5ba57c53603b0b07b56bba752f7784bf506fa95edc395f5cf6c7514fe9d").unwrap())
+                .unwrap();
+        let address: H160 = "008aeeda4d805471df9b2a5b0f38a0c3bcba786b".parse().unwrap();
+        let salt: [u8; 32] = hex::decode(salt).unwrap().try_into().unwrap();
+        let iv: [u8; 16] = hex::decode(iv).unwrap().try_into().unwrap();
+
+        let json = encrypt_with(&private_key, &address, "testpassword", kdf, &salt, &iv, [0; 16]);
+        assert!(json.contains(&format!(r#""ciphertext":"{}""#, ciphertext)), "{}", json);
+        assert!(json.contains(&format!(r#""mac":"{}""#, mac)), "{}", json);
+        assert!(json.contains(&format!(r#""salt":"{}""#, hex::encode(salt))), "{}", json);
+    }
+
+    #[test]
+    fn pbkdf2_test_vector() {
+        check_vector(
+            Kdf::Pbkdf2,
+            "ae3cd4e7013836a3df6bd7241b12db061dbe2c6785853cce422d148a624ce0bd",
+            "6087dab2f9fdbbfaddc31a909735c1e6",
+            "5318b4d5bcd28de64ee5559e671353e16f075ecae9f99c7a79a38af5f869aa46",
+            "517ead924a9d0dc3124507e3393d175ce3ff7c1e96529c6c555ce9e51205e9b2",
+        );
+    }
+
+    /// Reads a field of the flat JSON this module writes.
+    fn field<'a>(json: &'a str, name: &str) -> &'a str {
+        let start = json.find(&format!(r#""{}":"#, name)).unwrap() + name.len() + 3;
+        let value = &json[start..];
+        let end = value.find([',', '}']).unwrap();
+        value[..end].trim_matches('"')
+    }
+
+    /// geth's scrypt parameters have no official test vector, so decrypt
+    /// with the parameters the file advertises, the way a wallet would.
+    #[test]
+    fn scrypt_round_trip() {
+        let private_key = SecretKey::new(&mut OsRng);
+        let address = H160::random();
+        let json = encrypt(&private_key, &address, "passphrase", Kdf::Scrypt);
+
+        let n: u64 = field(&json, "n").parse().unwrap();
+        let r = field(&json, "r").parse().unwrap();
+        let p = field(&json, "p").parse().unwrap();
+        let salt = hex::decode(field(&json, "salt")).unwrap();
+        let params = scrypt::Params::new(n.trailing_zeros() as u8, r, p, 32).unwrap();
+        let mut derived_key = [0u8; 32];
+        scrypt::scrypt(b"passphrase", &salt, &params, &mut derived_key).unwrap();
+
+        let mut key = hex::decode(field(&json, "ciphertext")).unwrap();
+        let mut mac = Keccak256::new();
+        mac.update(&derived_key[16..]);
+        mac.update(&key);
+        assert_eq!(field(&json, "mac"), hex::encode(mac.finalize()));
+
+        let iv: [u8; 16] = hex::decode(field(&json, "iv")).unwrap().try_into().unwrap();
+        ctr::Ctr128BE::<Aes128>::new(derived_key[..16].into(), &iv.into()).apply_keystream(&mut key);
+        assert_eq!(key, private_key.secret_bytes());
+        assert_eq!(field(&json, "address"), format!("{:x}", address));
+    }
+}
@@ -0,0 +1,49 @@
+//! Ethereum vanity address search.
+//!
+//! A [`Searcher`] runs a search on a pool of threads. Each thread draws
+//! batches of candidate addresses from its own [`Worker`] (private keys,
+//! CREATE2 salts, mnemonics, ...) and keeps the ones a [`Matcher`] accepts,
+//! streaming them as [`Hit`]s.
+//!
+//! Prefixes, suffixes, masks, regexes and scores are all matchers, and any
+//! of them combine with `and`, `or` and `not`:
+//!
+//! ```no_run
+//! use eth_key_gen::{Candidate, Matcher, Pattern, Predicate, SearchConfig, Searcher, Secret};
+//!
+//! # fn main() -> Result<(), String> {
+//! let matcher = Pattern::new(Some("cafe"), None, false)?
+//!     .or(Pattern::new(None, Some("cafe"), false)?)
+//!     .and(Predicate(|candidate: &Candidate| candidate.bytes()[10] == 0).not());
+//!
+//! let searcher = Searcher::new(SearchConfig::default(), matcher)?;
+//! for hit in searcher.start_keys() {
+//!     if let Secret::PrivateKey(private_key) = &hit.secret {
+//!         println!("0x{:x}: {}", hit.address, hex::encode(private_key.secret_bytes()));
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod abi;
+pub mod account;
+pub mod checksum;
+pub mod contract;
+pub mod difficulty;
+pub mod hd;
+pub mod hooks;
+pub mod keystore;
+pub mod matcher;
+pub mod mnemonic;
+pub mod safe;
+pub mod score;
+pub mod search;
+pub mod searcher;
+pub mod secure;
+pub mod split;
+
+pub use difficulty::Difficulty;
+pub use matcher::{And, Candidate, Criteria, Matcher, Not, Or, Pattern, PatternSet, Predicate};
+pub use search::{Secret, Worker};
+pub use searcher::{CancelHandle, Hit, Search, SearchConfig, SearchResults, Searcher, Stats};
@@ -1,181 +1,991 @@
-use clap::Parser;
-use ethereum_types::H160;
+use clap::{Parser, Subcommand};
+use eth_key_gen::account::simple_account_init_code_hash;
+use eth_key_gen::checksum::to_checksum_address;
+use eth_key_gen::contract::{Create2Miner, Create3Miner, CreateMiner, CREATE3_PROXY_INIT_CODE_HASH, check_nonces};
+use eth_key_gen::difficulty::Difficulty;
+use eth_key_gen::hd::{parse_path, ChildScanner, ExtendedPrivateKey, ExtendedPublicKey, DEFAULT_PATH, HARDENED};
+use eth_key_gen::hooks::{hook_flag_names, hook_pattern, parse_hook_flags};
+use eth_key_gen::keystore::{Kdf, Keystore};
+use eth_key_gen::matcher::{self, AddressRegex, Candidate, Criteria, Matcher, Pattern, PatternSet, Predicate, ZeroBytes};
+use eth_key_gen::mnemonic::{check_word_count, MnemonicMiner};
+use eth_key_gen::safe::{setup_calldata, validate_owners, SafeMiner};
+use eth_key_gen::score::{Scorer, Scoring};
+use eth_key_gen::search::{public_key_address, KeyWalker, Secret};
+use eth_key_gen::searcher::{Hit, Search, SearchConfig, Searcher};
+use eth_key_gen::secure;
+use eth_key_gen::split::{self, SplitKeyMiner, SplitScheme};
+use ethereum_types::{H160, U256};
 use indicatif::{ProgressBar, ProgressStyle};
-use rand::rngs::OsRng;
-use rayon::prelude::*;
-use secp256k1::{Secp256k1, SecretKey};
-use sha3::{Digest, Keccak256};
-use std::sync::atomic::{AtomicU64, AtomicBool, Ordering};
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use zeroize::Zeroizing;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Desired address prefix (without 0x)
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     prefix: Option<String>,
 
     /// Desired address suffix
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     suffix: Option<String>,
 
+    /// Positional mask of hex characters and `?` wildcards, e.g. `0x00..00` or `dead????...beef`
+    #[arg(short, long, global = true)]
+    mask: Option<String>,
+
+    /// File with one prefix or mask per line, all searched for at once
+    #[arg(short = 'f', long, conflicts_with_all = ["prefix", "suffix", "mask"], global = true)]
+    patterns_file: Option<String>,
+
+    /// Regular expression the 40 hex characters of the address must match
+    #[arg(short, long, global = true)]
+    regex: Option<String>,
+
+    /// Minimum number of zero bytes at the start of the address
+    #[arg(short = 'z', long, conflicts_with = "zero_bytes", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=20), global = true)]
+    leading_zero_bytes: Option<usize>,
+
+    /// Minimum number of zero bytes anywhere in the address
+    #[arg(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..=20), global = true)]
+    zero_bytes: Option<usize>,
+
+    /// Match the case of the pattern against the EIP-55 checksum address
+    #[arg(short, long, global = true)]
+    case_sensitive: bool,
+
     /// Number of threads to use (default: number of CPU cores)
-    #[arg(short, long)]
+    #[arg(short, long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), global = true)]
     threads: Option<usize>,
     
     /// Number of addresses to generate (default: 1)
-    #[arg(short, long, default_value_t = 1)]
+    #[arg(short, long, default_value_t = 1, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), global = true)]
     quantity: usize,
+
+    /// Keep the best-scoring addresses instead of stopping at exact matches
+    #[arg(long, value_enum, global = true)]
+    score: Option<Scoring>,
+
+    /// Target address for `--score target-prefix`
+    #[arg(long, global = true)]
+    target: Option<String>,
+
+    /// Number of addresses kept on the leaderboard in `--score` mode
+    #[arg(long, default_value_t = 10, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), global = true)]
+    top: usize,
+
+    /// Stop searching after this many seconds
+    #[arg(long, global = true)]
+    duration: Option<u64>,
+
+    /// Stop searching after this many attempts
+    #[arg(long, global = true)]
+    max_attempts: Option<u64>,
+
+    /// Number of candidates each thread processes per batch (keys share one field inversion per batch)
+    #[arg(short, long, default_value_t = 1024, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..), global = true)]
+    batch_size: usize,
+
+    /// Write found private keys as encrypted keystore V3 files into this directory instead of printing them
+    #[arg(long, global = true)]
+    keystore_dir: Option<String>,
+
+    /// Key derivation function of the keystore files
+    #[arg(long, value_enum, default_value_t = Kdf::Scrypt, requires = "keystore_dir", global = true)]
+    kdf: Kdf,
+
+    /// File whose first line is the keystore passphrase (prompted for otherwise)
+    #[arg(long, requires = "keystore_dir", global = true)]
+    passphrase_file: Option<String>,
+
+    /// Print the raw private keys even though they are written to keystore files
+    #[arg(long, requires = "keystore_dir", global = true)]
+    show_private_keys: bool,
+
+    /// Only print the odds of a match and the expected search time, measured over a short benchmark
+    #[arg(long, conflicts_with = "score", global = true)]
+    estimate: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Mine a CREATE2 salt for a vanity contract address
+    Create2 {
+        /// Address of the deploying contract (factory)
+        #[arg(long, value_parser = parse_address)]
+        deployer: H160,
+
+        /// Keccak-256 hash of the contract's init code
+        #[arg(long, value_parser = parse_bytes32)]
+        init_code_hash: [u8; 32],
+    },
+    /// Mine a CREATE2 salt for a Uniswap v4 hook whose address encodes exactly the given permissions
+    Hook {
+        /// Address of the deploying contract (defaults to the deterministic deployment proxy)
+        #[arg(long, value_parser = parse_address, default_value = "0x4e59b44847b379578588920ca78fbf26c0b4956c")]
+        deployer: H160,
+
+        /// Keccak-256 hash of the hook's init code, including constructor arguments
+        #[arg(long, value_parser = parse_bytes32)]
+        init_code_hash: [u8; 32],
+
+        /// Comma-separated hook permissions, e.g. `beforeSwap,afterAddLiquidity`
+        #[arg(long, value_parser = parse_hook_flags, default_value = "")]
+        flags: u16,
+    },
+    /// Mine a CREATE3 salt for a vanity contract address
+    Create3 {
+        /// Address of the contract that deploys the proxy; the salt is mined as its CREATE2 sees it
+        #[arg(long, value_parser = parse_address)]
+        factory: H160,
+
+        /// Keccak-256 hash of the factory's proxy init code
+        #[arg(long, value_parser = parse_bytes32, default_value = CREATE3_PROXY_INIT_CODE_HASH)]
+        proxy_init_code_hash: [u8; 32],
+    },
+    /// Mine the saltNonce of a Safe proxy deployment for a vanity multisig address
+    Safe {
+        /// Comma-separated owner addresses, all distinct and none of them 0x0 or 0x…01
+        #[arg(long, value_parser = parse_address, value_delimiter = ',', required = true)]
+        owners: Vec<H160>,
+
+        /// Number of owner signatures required
+        #[arg(long, default_value_t = 1)]
+        threshold: usize,
+
+        /// Address of the Safe proxy factory
+        #[arg(long, value_parser = parse_address)]
+        proxy_factory: H160,
+
+        /// Address of the Safe singleton (master copy) the proxy delegates to
+        #[arg(long, value_parser = parse_address)]
+        singleton: H160,
+
+        /// Address of the fallback handler set up in the Safe
+        #[arg(long, value_parser = parse_address)]
+        fallback_handler: H160,
+
+        /// Proxy creation code, as returned by the factory's `proxyCreationCode()`
+        #[arg(long, value_parser = parse_bytes)]
+        proxy_creation_code: Bytes,
+    },
+    /// Mine the salt of an ERC-4337 account factory for a vanity smart account address
+    Account {
+        /// Address of the account factory
+        #[arg(long, value_parser = parse_address)]
+        factory: H160,
+
+        /// Owner of the account
+        #[arg(long, value_parser = parse_address, required_unless_present = "init_code_hash")]
+        owner: Option<H160>,
+
+        /// Address of the account implementation behind the proxy
+        #[arg(long, value_parser = parse_address, required_unless_present = "init_code_hash")]
+        implementation: Option<H160>,
+
+        /// Creation code of the account proxy (ERC1967Proxy for SimpleAccountFactory)
+        #[arg(long, value_parser = parse_bytes, required_unless_present = "init_code_hash")]
+        proxy_creation_code: Option<Bytes>,
+
+        /// Init code hash of the account, for factories that don't follow SimpleAccountFactory
+        #[arg(long, value_parser = parse_bytes32, conflicts_with_all = ["owner", "implementation", "proxy_creation_code"])]
+        init_code_hash: Option<[u8; 32]>,
+    },
+    /// Mine a private key whose CREATE deployment gets a vanity contract address
+    Create {
+        /// Deployment nonce, or an inclusive range such as `0-4` to accept any of them
+        #[arg(long, default_value = "0", value_parser = parse_nonces)]
+        nonce: RangeInclusive<u64>,
+    },
+    /// Generate BIP-39 mnemonics whose derived account has a vanity address (much slower than raw keys)
+    Mnemonic {
+        /// BIP-32 derivation path of the account
+        #[arg(long, value_parser = parse_derivation_path, default_value = DEFAULT_PATH)]
+        path: DerivationPath,
+
+        /// Number of words in the mnemonic
+        #[arg(long, default_value_t = 12, value_parser = parse_word_count)]
+        words: usize,
+    },
+    /// Scan the child indexes of an existing wallet for a vanity address, creating no new secret
+    Child {
+        /// Extended public key whose non-hardened children are scanned
+        #[arg(long, value_parser = ExtendedPublicKey::from_base58, required_unless_present_any = ["seed", "seed_file"])]
+        xpub: Option<ExtendedPublicKey>,
+
+        /// Derive the parent key from a BIP-39 seed (not the mnemonic) in hex, prompted for without echo
+        #[arg(long, conflicts_with = "xpub")]
+        seed: bool,
+
+        /// File whose first line is the BIP-39 seed in hex; implies --seed
+        #[arg(long, conflicts_with = "xpub")]
+        seed_file: Option<String>,
+
+        /// Derivation path of the parent key; only used to label the results when scanning an xpub
+        #[arg(long, value_parser = parse_derivation_path, default_value = "m/44'/60'/0'/0")]
+        path: DerivationPath,
+
+        /// First child index to scan
+        #[arg(long, default_value_t = 0, value_parser = clap::value_parser!(u32).range(..HARDENED as i64))]
+        start: u32,
+    },
+    /// Search for a partial key that gives a vanity address when added to (or multiplied with) somebody else's key
+    Split {
+        /// Public key of the requester, compressed or uncompressed hex
+        #[arg(long, value_parser = parse_public_key)]
+        public_key: PublicKey,
+
+        /// How the requester's key and the partial key combine into the final key
+        #[arg(long, value_enum, default_value_t = SplitScheme::Additive)]
+        scheme: SplitScheme,
+    },
+    /// Combine your private key with a partial key found by `split` and verify the result
+    Combine {
+        /// File whose first line is your private key, whose public key was passed to `split` (prompted for otherwise)
+        #[arg(long)]
+        private_key_file: Option<String>,
+
+        /// Partial private key reported by `split`
+        #[arg(long, value_parser = parse_secret_key)]
+        partial_key: SecretKey,
+
+        /// Address reported by `split`, checked against the combined key
+        #[arg(long, value_parser = parse_address)]
+        address: Option<H160>,
+
+        /// Scheme the partial key was searched with
+        #[arg(long, value_enum, default_value_t = SplitScheme::Additive)]
+        scheme: SplitScheme,
+    },
 }
 
-#[derive(Clone)]
-struct KeyPair {
-    private_key: SecretKey,
-    address: String,
+fn parse_address(input: &str) -> Result<H160, String> {
+    let bytes = hex::decode(input.strip_prefix("0x").unwrap_or(input)).map_err(|err| err.to_string())?;
+    if bytes.len() != 20 {
+        return Err("expected 20 bytes of hex".to_string());
+    }
+    Ok(H160::from_slice(&bytes))
 }
 
-fn generate_key_pair(secp: &Secp256k1<secp256k1::All>) -> KeyPair {
-    let secret_key = SecretKey::new(&mut OsRng);
-    let public_key = secp256k1::PublicKey::from_secret_key(&secp, &secret_key);
-    
-    let public_key_bytes = public_key.serialize_uncompressed();
-    let public_key_hash = Keccak256::digest(&public_key_bytes[1..]);
-    let address = H160::from_slice(&public_key_hash[12..32]);
-    
-    KeyPair {
-        private_key: secret_key,
-        address: format!("0x{:x}", address),
+fn parse_public_key(input: &str) -> Result<PublicKey, String> {
+    let bytes = hex::decode(input.strip_prefix("0x").unwrap_or(input)).map_err(|err| err.to_string())?;
+    PublicKey::from_slice(&bytes).map_err(|_| "expected a 33 or 65 byte secp256k1 public key".to_string())
+}
+
+fn parse_secret_key(input: &str) -> Result<SecretKey, String> {
+    let bytes = Zeroizing::new(hex::decode(input.strip_prefix("0x").unwrap_or(input)).map_err(|err| err.to_string())?);
+    SecretKey::from_slice(&bytes).map_err(|_| "expected a 32 byte secp256k1 private key".to_string())
+}
+
+/// Hex-decoded bytes, wrapped so clap doesn't treat them as a list of arguments.
+#[derive(Clone, Debug)]
+struct Bytes(Vec<u8>);
+
+fn parse_bytes(input: &str) -> Result<Bytes, String> {
+    hex::decode(input.strip_prefix("0x").unwrap_or(input))
+        .map(Bytes)
+        .map_err(|err| err.to_string())
+}
+
+/// Child indexes of a BIP-32 path, wrapped so clap doesn't treat them as a list of arguments.
+#[derive(Clone, Debug)]
+struct DerivationPath(Vec<u32>);
+
+fn parse_derivation_path(input: &str) -> Result<DerivationPath, String> {
+    parse_path(input).map(DerivationPath)
+}
+
+fn parse_seed(input: &str) -> Result<Zeroizing<Vec<u8>>, String> {
+    let seed = Zeroizing::new(parse_bytes(input)?.0);
+    if !(16..=64).contains(&seed.len()) {
+        return Err("seed must be 16 to 64 bytes of hex".to_string());
     }
+    Ok(seed)
 }
 
-fn matches_criteria(address: &str, prefix: &Option<String>, suffix: &Option<String>) -> bool {
-    let addr_without_prefix = &address[2..]; // Remove "0x" prefix
-    
-    if let Some(prefix) = prefix {
-        if !addr_without_prefix.to_lowercase().starts_with(&prefix.to_lowercase()) {
-            return false;
+/// Wipes the partial key given on the command line once it has been used,
+/// rather than keeping it in `Args` for the rest of the run.
+fn wipe_secret_args(args: &mut Args) {
+    if let Some(Command::Combine { partial_key, .. }) = &mut args.command {
+        partial_key.non_secure_erase();
+    }
+}
+
+fn parse_word_count(input: &str) -> Result<usize, String> {
+    let words = input.parse().map_err(|_| format!("invalid word count '{}'", input))?;
+    check_word_count(words)?;
+    Ok(words)
+}
+
+fn parse_nonces(input: &str) -> Result<RangeInclusive<u64>, String> {
+    let (start, end) = input.split_once('-').unwrap_or((input, input));
+    let start: u64 = start.trim().parse().map_err(|_| format!("invalid nonce '{}'", start))?;
+    let end: u64 = end.trim().parse().map_err(|_| format!("invalid nonce '{}'", end))?;
+    check_nonces(&(start..=end))?;
+    Ok(start..=end)
+}
+
+fn parse_bytes32(input: &str) -> Result<[u8; 32], String> {
+    let bytes = hex::decode(input.strip_prefix("0x").unwrap_or(input)).map_err(|err| err.to_string())?;
+    bytes.try_into().map_err(|_| "expected 32 bytes of hex".to_string())
+}
+
+fn build_criteria(args: &Args) -> Result<Criteria, String> {
+    let patterns = match &args.patterns_file {
+        Some(path) => PatternSet::from_file(path, args.case_sensitive)?,
+        None => {
+            let mut pattern = Pattern::new(args.prefix.as_deref(), args.suffix.as_deref(), args.case_sensitive)?;
+            if let Some(mask) = &args.mask {
+                pattern.merge(&Pattern::from_mask(mask, args.case_sensitive)?)?;
+            }
+            let mut patterns = PatternSet::default();
+            patterns.add(String::new(), pattern);
+            patterns
+        }
+    };
+    let regex = args
+        .regex
+        .as_deref()
+        .map(|regex| AddressRegex::new(regex, args.case_sensitive))
+        .transpose()?;
+
+    let zero_bytes = match (args.leading_zero_bytes, args.zero_bytes) {
+        (Some(count), _) => Some(ZeroBytes::Leading(count)),
+        (None, Some(count)) => Some(ZeroBytes::Anywhere(count)),
+        (None, None) => None,
+    };
+
+    let required = match args.command {
+        Some(Command::Hook { flags, .. }) => Some(hook_pattern(flags)),
+        _ => None,
+    };
+
+    Ok(Criteria { required, patterns, regex, zero_bytes })
+}
+
+fn print_hit(args: &Args, criteria: &Criteria, keystore: Option<&Keystore>, hit: &Hit) {
+    match &hit.secret {
+        Secret::PrivateKey(private_key) => print_private_key(args, keystore, private_key, &hit.address),
+        Secret::Salt(salt) => println!("Salt: 0x{}", hex::encode(salt)),
+        Secret::SaltNonce(salt_nonce) => println!("Salt Nonce: {}", U256::from_big_endian(salt_nonce)),
+        Secret::Deployer { private_key, address, nonce } => {
+            print_private_key(args, keystore, private_key, address);
+            println!("Deployer: {}", format_address(args, address));
+            println!("Nonce: {}", nonce);
+        }
+        Secret::Mnemonic { phrase, private_key } => {
+            println!("Mnemonic: {}", phrase);
+            if let Some(Command::Mnemonic { path, .. }) = &args.command {
+                println!("Derivation Path: {}", format_path(&path.0));
+            }
+            print_private_key(args, keystore, private_key, &hit.address);
+        }
+        Secret::PartialKey(partial_key) => println!("Partial Private Key: {}", hex::encode(partial_key.secret_bytes())),
+        Secret::ChildIndex(index) => {
+            println!("Index: {}", index);
+            if let Some(Command::Child { path, .. }) = &args.command {
+                println!("Derivation Path: {}/{}", format_path(&path.0), index);
+            }
         }
     }
-    
-    if let Some(suffix) = suffix {
-        if !addr_without_prefix.to_lowercase().ends_with(&suffix.to_lowercase()) {
-            return false;
+    println!("Address: {}", format_address(args, &hit.address));
+    if let (Some(_), Some(pattern)) = (&args.patterns_file, criteria.patterns.find(&Candidate::new(&hit.address))) {
+        println!("Pattern: {}", criteria.patterns.label(pattern));
+    }
+    if criteria.zero_bytes.is_some() {
+        println!(
+            "Zero bytes: {} leading, {} total",
+            matcher::leading_zero_bytes(&hit.address),
+            matcher::zero_bytes(&hit.address)
+        );
+        if matcher::has_partial_zero_nibble(&hit.address) {
+            println!("Note: the next byte also starts with a zero nibble, which does not reduce gas");
         }
     }
+}
+
+/// Prints a found private key, or writes it to the keystore and prints the
+/// file instead. The key is printed anyway if it couldn't be saved.
+fn print_private_key(args: &Args, keystore: Option<&Keystore>, private_key: &SecretKey, address: &H160) {
+    let saved = keystore.is_some_and(|keystore| match keystore.write(private_key, address) {
+        Ok(path) => {
+            println!("Keystore: {}", path.display());
+            true
+        }
+        Err(err) => {
+            // The directory was writable when the search started; rather than
+            // lose the key, show it
+            eprintln!("Error: {}; printing the private key instead so it isn't lost", err);
+            false
+        }
+    });
+    if !saved || args.show_private_keys {
+        let hex = Zeroizing::new(hex::encode(Zeroizing::new(private_key.secret_bytes()).as_slice()));
+        println!("Private Key: {}", hex.as_str());
+    }
+}
+
+/// Whether the search ends in private keys that can go into a keystore.
+fn finds_private_keys(args: &Args) -> bool {
+    matches!(
+        args.command,
+        None | Some(Command::Create { .. } | Command::Mnemonic { .. } | Command::Combine { .. })
+    )
+}
+
+/// Reads a secret from the first line of the file at `path`, or without echo
+/// from the terminal if there is none, so that it stays out of the shell
+/// history and process listings.
+fn read_secret(path: Option<&str>, prompt: &str) -> Result<Zeroizing<String>, String> {
+    match path {
+        Some(path) => {
+            let contents =
+                Zeroizing::new(std::fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path, err))?);
+            Ok(Zeroizing::new(contents.lines().next().unwrap_or_default().to_string()))
+        }
+        None => Ok(Zeroizing::new(rpassword::prompt_password(prompt).map_err(|err| err.to_string())?)),
+    }
+}
+
+/// Opens the `--keystore-dir`, reading the passphrase from `--passphrase-file`
+/// or prompting for it twice.
+fn open_keystore(args: &Args, dir: &str) -> Result<Keystore, String> {
+    if !finds_private_keys(args) {
+        return Err("--keystore-dir only applies to searches for private keys".to_string());
+    }
+
+    let passphrase = read_secret(args.passphrase_file.as_deref(), "Keystore passphrase: ")?;
+    if args.passphrase_file.is_none() && passphrase != read_secret(None, "Repeat passphrase: ")? {
+        return Err("passphrases do not match".to_string());
+    }
+
+    Keystore::new(Path::new(dir), args.kdf, passphrase)
+}
+
+fn format_address(args: &Args, address: &H160) -> String {
+    if args.case_sensitive {
+        to_checksum_address(address)
+    } else {
+        format!("0x{:x}", address)
+    }
+}
+
+fn format_path(path: &[u32]) -> String {
+    let mut formatted = "m".to_string();
+    for &index in path {
+        if index >= HARDENED {
+            formatted += &format!("/{}'", index - HARDENED);
+        } else {
+            formatted += &format!("/{}", index);
+        }
+    }
+    formatted
+}
+
+/// Init code hash of the smart account the `account` subcommand mines for.
+fn account_init_code_hash(
+    owner: &Option<H160>,
+    implementation: &Option<H160>,
+    proxy_creation_code: &Option<Bytes>,
+    init_code_hash: &Option<[u8; 32]>,
+) -> [u8; 32] {
+    match (owner, implementation, proxy_creation_code, init_code_hash) {
+        (_, _, _, Some(init_code_hash)) => *init_code_hash,
+        (Some(owner), Some(implementation), Some(proxy_creation_code), None) => {
+            simple_account_init_code_hash(&proxy_creation_code.0, implementation, owner)
+        }
+        _ => unreachable!("clap requires either --init-code-hash or all of its parts"),
+    }
+}
+
+/// Human-readable length of time, from seconds.
+fn format_eta(seconds: f64) -> String {
+    const YEAR: f64 = 365.25 * 86400.0;
+    let whole = seconds as u64;
+    match seconds {
+        s if !s.is_finite() => "never".to_string(),
+        s if s < 1.0 => "< 1s".to_string(),
+        s if s < 60.0 => format!("{}s", whole),
+        s if s < 3600.0 => format!("{}m {}s", whole / 60, whole % 60),
+        s if s < 86400.0 => format!("{}h {}m", whole / 3600, whole % 3600 / 60),
+        s if s < YEAR => format!("{}d {}h", whole / 86400, whole % 86400 / 3600),
+        s if s < 1e6 * YEAR => format!("{:.0} years", s / YEAR),
+        s => format!("{:.2e} years", s / YEAR),
+    }
+}
+
+/// Large counts in scientific notation.
+fn format_count(count: f64) -> String {
+    if count < 1e12 {
+        format!("{:.0}", count)
+    } else {
+        format!("{:.2e}", count)
+    }
+}
+
+/// The 50%, 90% and 99% ETAs for `hits` more matches.
+fn format_etas(difficulty: &Difficulty, hits: usize, rate: f64) -> String {
+    let etas: Vec<String> = [0.5, 0.9, 0.99]
+        .iter()
+        .map(|&confidence| format_eta(difficulty.eta(hits, confidence, rate)))
+        .collect();
+    format!("{} / {} / {} (50/90/99%)", etas[0], etas[1], etas[2])
+}
+
+fn candidate_name(args: &Args) -> &'static str {
+    match args.command {
+        None | Some(Command::Create { .. } | Command::Split { .. } | Command::Combine { .. }) => "keys",
+        Some(Command::Mnemonic { .. }) => "mnemonics",
+        Some(Command::Child { .. }) => "children",
+        Some(_) => "salts",
+    }
+}
+
+/// Sets up a search with the options shared by all subcommands, showing its
+/// progress on the spinner.
+fn new_searcher(args: &Args, criteria: Arc<Criteria>, scorer: Option<Scorer>, num_threads: usize, pb: ProgressBar) -> Searcher {
+    let config = SearchConfig {
+        threads: num_threads,
+        quantity: args.quantity,
+        top: scorer.as_ref().map(|_| args.top),
+        duration: args.duration.map(Duration::from_secs),
+        max_attempts: args.max_attempts,
+        batch_size: args.batch_size,
+    };
     
-    true
+    // Update progress and stats every 100ms
+    let scoring = config.top.is_some();
+    let difficulty = criteria.probability().map(Difficulty::new);
+    let case_sensitive = args.case_sensitive;
+    let quantity = args.quantity;
+    let unit = candidate_name(args);
+    // Scoring ranks the addresses that meet the criteria
+    let matcher: Box<dyn Matcher> = match scorer {
+        Some(scorer) => Box::new(criteria.and(scorer)),
+        None => Box::new(criteria),
+    };
+    let searcher = Searcher::new(config, matcher).expect("clap checks the search options");
+    searcher.on_stats(Duration::from_millis(100), move |stats| {
+        if scoring {
+            let best = match stats.best {
+                Some((score, address)) if case_sensitive => format!("{} ({})", score, to_checksum_address(&address)),
+                Some((score, address)) => format!("{} (0x{:x})", score, address),
+                None => "-".to_string(),
+            };
+            pb.set_message(format!("{:.2} {}/s | Best: {}", stats.rate(), unit, best));
+        } else {
+            let mut message = format!("{:.2} {}/s | Found: {}/{}", stats.rate(), unit, stats.found, quantity);
+            if let Some(difficulty) = &difficulty {
+                message += &format!(" | ETA {}", format_etas(difficulty, quantity - stats.found, stats.rate()));
+            }
+            pb.set_message(message);
+        }
+    })
+}
+
+/// Starts the search the subcommand asks for, one worker per thread.
+fn start_search(args: &Args, searcher: Searcher, parent: Option<ExtendedPublicKey>) -> Search {
+    let batch_size = args.batch_size;
+    match &args.command {
+        None => searcher.start_keys(),
+        Some(Command::Create2 { deployer, init_code_hash } | Command::Hook { deployer, init_code_hash, .. }) => {
+            let (deployer, init_code_hash) = (*deployer, *init_code_hash);
+            searcher.start(move || Create2Miner::random(&deployer, &init_code_hash, batch_size))
+        }
+        Some(Command::Create3 { factory, proxy_init_code_hash }) => {
+            let (factory, proxy_init_code_hash) = (*factory, *proxy_init_code_hash);
+            searcher.start(move || Create3Miner::new(Create2Miner::random(&factory, &proxy_init_code_hash, batch_size)))
+        }
+        Some(Command::Safe { owners, threshold, proxy_factory, singleton, fallback_handler, proxy_creation_code }) => {
+            let initializer = setup_calldata(owners, *threshold, fallback_handler);
+            let (proxy_factory, singleton, proxy_creation_code) = (*proxy_factory, *singleton, proxy_creation_code.clone());
+            searcher.start(move || {
+                SafeMiner::random(&proxy_factory, &singleton, &proxy_creation_code.0, &initializer, batch_size)
+            })
+        }
+        Some(Command::Account { factory, owner, implementation, proxy_creation_code, init_code_hash }) => {
+            let init_code_hash = account_init_code_hash(owner, implementation, proxy_creation_code, init_code_hash);
+            let factory = *factory;
+            searcher.start(move || Create2Miner::random(&factory, &init_code_hash, batch_size))
+        }
+        Some(Command::Create { nonce }) => {
+            let nonce = nonce.clone();
+            searcher.start(move || {
+                CreateMiner::new(KeyWalker::random(&Secp256k1::new(), batch_size), nonce.clone())
+                    .expect("parse_nonces checks the range")
+            })
+        }
+        Some(Command::Mnemonic { path, words }) => {
+            let (path, words) = (path.0.clone(), *words);
+            searcher.start(move || MnemonicMiner::new(words, &path).expect("parse_word_count checks the word count"))
+        }
+        Some(Command::Split { public_key, scheme }) => {
+            let (public_key, scheme) = (*public_key, *scheme);
+            searcher.start(move || {
+                let secp = Secp256k1::new();
+                SplitKeyMiner::new(match scheme {
+                    SplitScheme::Additive => KeyWalker::random_offset(&secp, &public_key, batch_size),
+                    SplitScheme::Multiplicative => KeyWalker::random_multiple(&secp, &public_key, batch_size),
+                })
+            })
+        }
+        Some(Command::Combine { .. }) => unreachable!("combine doesn't search"),
+        Some(Command::Child { start, .. }) => {
+            let parent = parent.unwrap();
+            let next_index = Arc::new(AtomicU64::new(*start as u64));
+            searcher.start(move || ChildScanner::new(&parent, next_index.clone(), batch_size))
+        }
+    }
+}
+
+/// The `--estimate` dry run: works out the odds of a match from the criteria,
+/// then measures the speed by searching for nothing for a moment.
+fn print_estimate(args: &Args, criteria: Arc<Criteria>, num_threads: usize, parent: Option<ExtendedPublicKey>) {
+    const BENCHMARK: Duration = Duration::from_secs(2);
+
+    let difficulty = criteria.probability().map(Difficulty::new);
+    match &difficulty {
+        Some(difficulty) if difficulty.probability > 0.0 => {
+            println!("Difficulty: 1 in {}", format_count(1.0 / difficulty.probability));
+            println!("Expected attempts: {}", format_count(difficulty.expected_attempts(args.quantity)));
+        }
+        Some(_) => {
+            println!("Difficulty: no address can meet these criteria");
+            return;
+        }
+        None => println!("Difficulty: unknown, regexes can't be estimated"),
+    }
+
+    println!("\nMeasuring speed for {} seconds...", BENCHMARK.as_secs());
+    let config = SearchConfig {
+        threads: num_threads,
+        duration: Some(BENCHMARK),
+        batch_size: args.batch_size,
+        ..SearchConfig::default()
+    };
+    // Pays for evaluating the criteria, but never accepts anything
+    let matcher = criteria.and(Predicate(|_: &Candidate| false));
+    let searcher = Searcher::new(config, matcher).expect("clap checks the search options");
+    let results = start_search(args, searcher, parent).finish();
+    let rate = results.attempts as f64 / results.elapsed.as_secs_f64();
+    println!("Speed: {:.2} {}/s", rate, candidate_name(args));
+
+    if let Some(difficulty) = &difficulty {
+        println!("Expected time: {}", format_eta(difficulty.expected_attempts(args.quantity) / rate));
+        for confidence in [0.5, 0.9, 0.99] {
+            println!(
+                "{}% chance within: {}",
+                confidence * 100.0,
+                format_eta(difficulty.eta(args.quantity, confidence, rate))
+            );
+        }
+    }
+}
+
+/// The `combine` subcommand: puts together the two halves of a split key and
+/// checks that the result owns the address the search reported.
+fn combine_split_key(
+    args: &Args,
+    keystore: Option<&Keystore>,
+    scheme: SplitScheme,
+    private_key: &SecretKey,
+    partial_key: &SecretKey,
+    address: &Option<H160>,
+) {
+    let Some(mut combined) = split::combine(scheme, private_key, partial_key) else {
+        eprintln!("Error: the keys combine to an invalid key");
+        std::process::exit(1);
+    };
+    let combined_address = public_key_address(&PublicKey::from_secret_key(&Secp256k1::new(), &combined));
+
+    if let Some(address) = address {
+        if *address != combined_address {
+            eprintln!(
+                "Error: the combined key belongs to {}, not {}",
+                format_address(args, &combined_address),
+                format_address(args, address)
+            );
+            std::process::exit(1);
+        }
+    }
+
+    print_private_key(args, keystore, &combined, &combined_address);
+    println!("Address: {}", format_address(args, &combined_address));
+    if address.is_some() {
+        println!("Verified: the combined key owns the expected address");
+    }
+    combined.non_secure_erase();
+    println!("\nIMPORTANT: Store your private key securely and never share it with anyone!");
 }
 
 fn main() {
-    let args = Args::parse();
+    let mut args = Args::parse();
+    if let Err(err) = secure::disable_core_dumps() {
+        eprintln!("Warning: {}", err);
+    }
+    // A dry run writes no keys, so it shouldn't ask for a passphrase
+    let keystore_dir = args.keystore_dir.as_deref().filter(|_| !args.estimate);
+    let keystore = keystore_dir.map(|dir| open_keystore(&args, dir)).transpose().unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    });
+    if let Some(Command::Combine { private_key_file, partial_key, address, scheme }) = &args.command {
+        let private_key = read_secret(private_key_file.as_deref(), "Your private key: ")
+            .and_then(|input| parse_secret_key(input.trim()));
+        let mut private_key = private_key.unwrap_or_else(|err| {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        });
+        combine_split_key(&args, keystore.as_ref(), *scheme, &private_key, partial_key, address);
+        private_key.non_secure_erase();
+        wipe_secret_args(&mut args);
+        return;
+    }
+    let criteria = build_criteria(&args).unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    });
+    let scorer = args.score.map(|scoring| Scorer::new(scoring, args.target.as_deref())).transpose().unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    });
+    if scorer.is_some() && args.duration.is_none() && args.max_attempts.is_none() {
+        eprintln!("Error: --score runs until a budget is exhausted, so it needs --duration or --max-attempts");
+        std::process::exit(1);
+    }
+    if let Some(Command::Safe { owners, threshold, .. }) = &args.command {
+        if *threshold == 0 || *threshold > owners.len() {
+            eprintln!("Error: threshold must be between 1 and the number of owners ({})", owners.len());
+            std::process::exit(1);
+        }
+        if let Err(err) = validate_owners(owners) {
+            eprintln!("Error: {}", err);
+            std::process::exit(1);
+        }
+    }
+    let parent = match &args.command {
+        Some(Command::Child { xpub: Some(xpub), .. }) => Some(xpub.clone()),
+        Some(Command::Child { seed_file, path, .. }) => {
+            let seed = read_secret(seed_file.as_deref(), "BIP-39 seed (hex): ").and_then(|input| parse_seed(input.trim()));
+            let seed = seed.unwrap_or_else(|err| {
+                eprintln!("Error: {}", err);
+                std::process::exit(1);
+            });
+            let secp = Secp256k1::new();
+            let parent = ExtendedPrivateKey::master(&seed).and_then(|master| master.derive(&secp, &path.0));
+            let Some(parent) = parent else {
+                eprintln!("Error: the seed derives to an invalid key at {}", format_path(&path.0));
+                std::process::exit(1);
+            };
+            Some(parent.public(&secp))
+        }
+        _ => None,
+    };
+    wipe_secret_args(&mut args);
     let num_threads = args.threads.unwrap_or_else(num_cpus::get);
-    rayon::ThreadPoolBuilder::new().num_threads(num_threads).build_global().unwrap();
     
     println!("Ethereum Vanity Address Generator");
     println!("--------------------------------");
     println!("Using {} threads", num_threads);
-    println!("Generating {} address(es)", args.quantity);
+    if let Some(Command::Create2 { deployer, init_code_hash }) = &args.command {
+        println!("Mining CREATE2 salt for deployer 0x{:x}", deployer);
+        println!("Init code hash: 0x{}", hex::encode(init_code_hash));
+    }
+    if let Some(Command::Hook { deployer, init_code_hash, flags }) = &args.command {
+        println!("Mining Uniswap v4 hook salt for deployer 0x{:x}", deployer);
+        println!("Init code hash: 0x{}", hex::encode(init_code_hash));
+        let names = hook_flag_names(*flags);
+        println!("Hook flags: {}", if names.is_empty() { "none".to_string() } else { names.join(", ") });
+    }
+    if let Some(Command::Create3 { factory, proxy_init_code_hash }) = &args.command {
+        println!("Mining CREATE3 salt for factory 0x{:x}", factory);
+        println!("Proxy init code hash: 0x{}", hex::encode(proxy_init_code_hash));
+    }
+    if let Some(Command::Safe { owners, threshold, proxy_factory, singleton, fallback_handler, .. }) = &args.command {
+        println!("Mining Safe saltNonce for proxy factory 0x{:x}", proxy_factory);
+        println!("Singleton: 0x{:x}", singleton);
+        println!("Owners: {}", owners.iter().map(|owner| format!("0x{:x}", owner)).collect::<Vec<_>>().join(", "));
+        println!("Threshold: {}", threshold);
+        println!("Fallback handler: 0x{:x}", fallback_handler);
+        println!("Initializer: 0x{}", hex::encode(setup_calldata(owners, *threshold, fallback_handler)));
+    }
+    if let Some(Command::Account { factory, owner, implementation, proxy_creation_code, init_code_hash }) = &args.command {
+        println!("Mining smart account salt for factory 0x{:x}", factory);
+        if let Some(owner) = owner {
+            println!("Owner: 0x{:x}", owner);
+        }
+        if let Some(implementation) = implementation {
+            println!("Implementation: 0x{:x}", implementation);
+        }
+        let init_code_hash = account_init_code_hash(owner, implementation, proxy_creation_code, init_code_hash);
+        println!("Init code hash: 0x{}", hex::encode(init_code_hash));
+    }
+    if let Some(Command::Create { nonce }) = &args.command {
+        println!("Mining deployer key for CREATE at nonce {}-{}", nonce.start(), nonce.end());
+    }
+    if let Some(Command::Mnemonic { path, words }) = &args.command {
+        println!("Mining {}-word BIP-39 mnemonic for account {}", words, format_path(&path.0));
+    }
+    if let Some(Command::Child { xpub, path, start, .. }) = &args.command {
+        let source = if xpub.is_some() { "extended public key" } else { "seed" };
+        println!("Scanning children of {} from {} at index {}", format_path(&path.0), source, start);
+    }
+    if let Some(Command::Split { public_key, scheme }) = &args.command {
+        println!("Searching {} partial key for public key 0x{}", scheme, hex::encode(public_key.serialize()));
+    }
+    if let Some(scoring) = args.score {
+        println!("Keeping the top {} address(es) by {}", args.top, scoring);
+    } else {
+        println!("Generating {} address(es)", args.quantity);
+    }
+    println!("Batch size: {}", args.batch_size);
     if let Some(prefix) = &args.prefix {
         println!("Looking for prefix: {}", prefix);
     }
     if let Some(suffix) = &args.suffix {
         println!("Looking for suffix: {}", suffix);
     }
+    if let Some(mask) = &args.mask {
+        println!("Looking for mask: {}", mask);
+    }
+    if let Some(path) = &args.patterns_file {
+        println!("Looking for {} patterns from {}", criteria.patterns.len(), path);
+    }
+    if let Some(regex) = &args.regex {
+        println!("Looking for regex: {}", regex);
+    }
+    if let Some(count) = args.leading_zero_bytes {
+        println!("Looking for {} leading zero bytes", count);
+    }
+    if let Some(count) = args.zero_bytes {
+        println!("Looking for {} zero bytes anywhere", count);
+    }
+    if args.case_sensitive {
+        println!("Matching EIP-55 checksum case");
+    }
+    if let Some(dir) = &args.keystore_dir {
+        println!("Writing keys to {} keystore files in {}", args.kdf, dir);
+    }
+    if let Some(duration) = args.duration {
+        println!("Time budget: {} seconds", duration);
+    }
+    if let Some(max_attempts) = args.max_attempts {
+        println!("Attempt budget: {}", max_attempts);
+    }
     println!();
+
+    if args.estimate {
+        print_estimate(&args, Arc::new(criteria), num_threads, parent);
+        return;
+    }
     
-    let found_keypairs = Arc::new(Mutex::new(Vec::with_capacity(args.quantity)));
-    let attempts = Arc::new(AtomicU64::new(0));
-    let completed = Arc::new(AtomicBool::new(false));
-    let start_time = Instant::now();
+    let criteria = Arc::new(criteria);
     let pb = ProgressBar::new_spinner();
-    
     pb.set_style(
         ProgressStyle::default_spinner()
             .template("{spinner:.green} [{elapsed_precise}] {msg}")
             .unwrap(),
     );
-    
-    // Update progress and stats every 100ms
-    let attempts_clone = attempts.clone();
-    let pb_clone = pb.clone();
-    let found_keypairs_clone = found_keypairs.clone();
-    let completed_clone = completed.clone();
-    std::thread::spawn(move || {
-        loop {
-            std::thread::sleep(Duration::from_millis(100));
-            
-            if completed_clone.load(Ordering::Relaxed) {
-                break;
-            }
-            
-            let current_attempts = attempts_clone.load(Ordering::Relaxed);
-            let elapsed = start_time.elapsed().as_secs_f64();
-            let speed = current_attempts as f64 / elapsed;
-            let found_count = found_keypairs_clone.lock().unwrap().len();
-            pb_clone.set_message(format!("{:.2} keys/s | Found: {}/{}", speed, found_count, args.quantity));
-        }
-    });
-    
-    // Generate addresses in parallel
-    (0..num_threads).into_par_iter().for_each(|_| {
-        let secp = Secp256k1::new();
-        let attempts = attempts.clone();
-        let found_keypairs = found_keypairs.clone();
-        let completed = completed.clone();
-        
-        loop {
-            // Check if we're done
-            if completed.load(Ordering::Relaxed) {
-                break;
-            }
-            
-            let keypair = generate_key_pair(&secp);
-            attempts.fetch_add(1, Ordering::Relaxed);
-            
-            if matches_criteria(&keypair.address, &args.prefix, &args.suffix) {
-                let mut found = found_keypairs.lock().unwrap();
-                
-                // Only add if we haven't reached the quantity
-                if found.len() < args.quantity {
-                    found.push(keypair);
-                    
-                    // If we've found all the addresses, mark as completed
-                    if found.len() >= args.quantity {
-                        completed.store(true, Ordering::Relaxed);
-                    }
-                }
-                
-                drop(found);
+    let searcher = new_searcher(&args, criteria.clone(), scorer, num_threads, pb.clone());
+
+    // The first Ctrl-C or SIGTERM stops the search but still reports (and
+    // saves) what it found; a second one quits on the spot
+    let interrupts = Arc::new(AtomicUsize::new(0));
+    let handler = {
+        let (interrupts, cancel, pb) = (interrupts.clone(), searcher.cancel_handle(), pb.clone());
+        ctrlc::set_handler(move || {
+            if interrupts.fetch_add(1, Ordering::SeqCst) > 0 {
+                std::process::exit(130);
             }
-        }
-    });
-    
-    // Mark as completed to stop the progress thread
-    completed.store(true, Ordering::Relaxed);
-    
+            pb.println("Interrupted, finishing up (press Ctrl-C again to quit immediately)");
+            cancel.cancel();
+        })
+    };
+    if let Err(err) = handler {
+        eprintln!("Warning: cannot handle Ctrl-C: {}", err);
+    }
+    let search = start_search(&args, searcher, parent);
+    let results = search.finish();
     pb.finish_and_clear();
+    if let Some(err) = &results.lock_error {
+        eprintln!("Warning: {}; found keys may be swapped to disk", err);
+    }
     
     // Print results
-    let total_attempts = attempts.load(Ordering::Relaxed);
-    let elapsed = start_time.elapsed().as_secs_f64();
-    let speed = if elapsed > 0.0 { total_attempts as f64 / elapsed } else { 0.0 };
-    let found_keypairs = found_keypairs.lock().unwrap();
+    let elapsed = results.elapsed.as_secs_f64();
+    let speed = if elapsed > 0.0 { results.attempts as f64 / elapsed } else { 0.0 };
     
-    if !found_keypairs.is_empty() {
-        println!("\nFound {} matching address(es)!", found_keypairs.len());
+    if !results.leaderboard.is_empty() {
+        println!("\nLeaderboard (top {} by {}):", results.leaderboard.len(), args.score.unwrap());
         
-        for (i, keypair) in found_keypairs.iter().enumerate() {
-            println!("\nAddress #{}", i + 1);
-            println!("Private Key: {}", hex::encode(keypair.private_key.secret_bytes()));
-            println!("Address: {}", keypair.address);
+        for (i, (score, hit)) in results.leaderboard.iter().enumerate() {
+            println!("\n#{} Score: {}", i + 1, score);
+            print_hit(&args, &criteria, keystore.as_ref(), hit);
         }
+    }
+    
+    if !results.found.is_empty() {
+        println!("\nFound {} matching address(es)!", results.found.len());
         
-        println!("\nStats:");
-        println!("Time taken: {:.2} seconds", elapsed);
-        println!("Total attempts: {}", total_attempts);
-        println!("Average speed: {:.2} keys/s", speed);
+        for (i, hit) in results.found.iter().enumerate() {
+            println!("\nAddress #{}", i + 1);
+            print_hit(&args, &criteria, keystore.as_ref(), hit);
+        }
     }
     
-    println!("\nIMPORTANT: Store your private key securely and never share it with anyone!");
-} 
\ No newline at end of file
+    if results.found.is_empty() && results.leaderboard.is_empty() {
+        println!("\nNo matching address found.");
+    }
+    let interrupted = interrupts.load(Ordering::SeqCst) > 0;
+    if interrupted {
+        println!("\nSearch interrupted.");
+    }
+    
+    println!("\nStats:");
+    println!("Time taken: {:.2} seconds", elapsed);
+    println!("Total attempts: {}", results.attempts);
+    println!("Average speed: {:.2} {}/s", speed, candidate_name(&args));
+    
+    match args.command {
+        None | Some(Command::Create { .. }) => println!("\nIMPORTANT: Store your private key securely and never share it with anyone!"),
+        Some(Command::Create2 { .. } | Command::Hook { .. }) => {
+            println!("\nDeploy through the same factory with the salt above to get this address.")
+        }
+        Some(Command::Create3 { .. }) => {
+            println!("\nDeploy through the same factory with the salt above to get this address.");
+            println!("The salt is the one the factory hands to CREATE2. Factories that mix msg.sender into the salt you pass (such as CREATE3Factory) give a different address for it.")
+        }
+        Some(Command::Account { .. }) => {
+            println!("\nCall createAccount(owner, salt) on the factory (or use it as the UserOperation initCode) to deploy this account.")
+        }
+        Some(Command::Mnemonic { .. }) => {
+            println!("\nIMPORTANT: Write down your mnemonic, store it securely and never share it with anyone!")
+        }
+        Some(Command::Split { .. }) => {
+            println!("\nSend the partial private key to the requester, who gets the final key with `combine`. It is useless without theirs.")
+        }
+        Some(Command::Combine { .. }) => unreachable!("combine doesn't search"),
+        Some(Command::Child { .. }) => {
+            println!("\nAdd the account at the path above in your wallet to use this address; no new key was created.")
+        }
+        Some(Command::Safe { .. }) => {
+            println!("\nCall createProxyWithNonce(singleton, initializer, saltNonce) on the proxy factory to deploy this Safe.")
+        }
+    }
+
+    // Tell scripts the run was cut short, wiping the keys first since
+    // exiting skips destructors
+    if interrupted {
+        drop(results);
+        drop(keystore);
+        std::process::exit(130);
+    }
+}
@@ -0,0 +1,1006 @@
+use crate::checksum::{checksum_hash, checksum_hex_with_hash, is_uppercase};
+use ethereum_types::H160;
+use fancy_regex::{Regex, RegexBuilder};
+use std::cell::OnceCell;
+use std::sync::Arc;
+
+/// Decides which candidate addresses a search is looking for, and how good
+/// they are.
+///
+/// Matchers run on every candidate from every search thread, so they should
+/// reject cheaply and avoid allocating. They combine with [`Matcher::and`],
+/// [`Matcher::or`] and [`Matcher::not`], and a closure becomes one through
+/// [`Predicate`].
+pub trait Matcher: Send + Sync {
+    /// `None` if the candidate isn't wanted, otherwise its score, higher
+    /// being better. Plain predicates score 0.
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32>;
+
+    fn matches(&self, candidate: &Candidate) -> bool {
+        self.evaluate(candidate).is_some()
+    }
+
+    /// Probability that a random address matches, if it can be worked out;
+    /// see [`Difficulty`](crate::difficulty::Difficulty).
+    fn probability(&self) -> Option<f64> {
+        None
+    }
+
+    /// Matches what both match, scoring the sum of both scores.
+    fn and<M: Matcher>(self, other: M) -> And<Self, M>
+    where
+        Self: Sized,
+    {
+        And(self, other)
+    }
+
+    /// Matches what either matches, scoring as the first that does.
+    fn or<M: Matcher>(self, other: M) -> Or<Self, M>
+    where
+        Self: Sized,
+    {
+        Or(self, other)
+    }
+
+    /// Matches what this doesn't.
+    fn not(self) -> Not<Self>
+    where
+        Self: Sized,
+    {
+        Not(self)
+    }
+}
+
+/// An address under test.
+///
+/// Its EIP-55 checksum costs a second Keccak hash, so it is only computed
+/// when the first matcher asks for it and then shared with the others.
+pub struct Candidate<'a> {
+    address: &'a H160,
+    checksum_hash: OnceCell<[u8; 32]>,
+    checksum: OnceCell<[u8; 40]>,
+}
+
+impl<'a> Candidate<'a> {
+    pub fn new(address: &'a H160) -> Self {
+        Candidate {
+            address,
+            checksum_hash: OnceCell::new(),
+            checksum: OnceCell::new(),
+        }
+    }
+
+    pub fn address(&self) -> &'a H160 {
+        self.address
+    }
+
+    /// The raw 20 bytes of the address.
+    pub fn bytes(&self) -> &'a [u8; 20] {
+        self.address.as_fixed_bytes()
+    }
+
+    /// Keccak hash of the lowercase hex address, which decides the checksum case.
+    pub fn checksum_hash(&self) -> &[u8; 32] {
+        self.checksum_hash.get_or_init(|| checksum_hash(self.address))
+    }
+
+    /// The 40 hex characters in EIP-55 checksum case, without `0x`.
+    pub fn checksum(&self) -> &str {
+        let hex = self
+            .checksum
+            .get_or_init(|| checksum_hex_with_hash(self.address, self.checksum_hash()));
+        std::str::from_utf8(hex).unwrap()
+    }
+}
+
+impl<M: Matcher + ?Sized> Matcher for Arc<M> {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        (**self).evaluate(candidate)
+    }
+
+    fn probability(&self) -> Option<f64> {
+        (**self).probability()
+    }
+}
+
+impl<M: Matcher + ?Sized> Matcher for Box<M> {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        (**self).evaluate(candidate)
+    }
+
+    fn probability(&self) -> Option<f64> {
+        (**self).probability()
+    }
+}
+
+/// See [`Matcher::and`].
+pub struct And<A, B>(pub A, pub B);
+
+impl<A: Matcher, B: Matcher> Matcher for And<A, B> {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        let score = self.0.evaluate(candidate)?;
+        Some(score.saturating_add(self.1.evaluate(candidate)?))
+    }
+
+    /// Assumes the two are independent.
+    fn probability(&self) -> Option<f64> {
+        Some(self.0.probability()? * self.1.probability()?)
+    }
+}
+
+/// See [`Matcher::or`].
+pub struct Or<A, B>(pub A, pub B);
+
+impl<A: Matcher, B: Matcher> Matcher for Or<A, B> {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        self.0.evaluate(candidate).or_else(|| self.1.evaluate(candidate))
+    }
+
+    /// Assumes the two are independent.
+    fn probability(&self) -> Option<f64> {
+        let (a, b) = (self.0.probability()?, self.1.probability()?);
+        Some(a + b - a * b)
+    }
+}
+
+/// See [`Matcher::not`].
+pub struct Not<M>(pub M);
+
+impl<M: Matcher> Matcher for Not<M> {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        match self.0.evaluate(candidate) {
+            Some(_) => None,
+            None => Some(0),
+        }
+    }
+
+    fn probability(&self) -> Option<f64> {
+        Some(1.0 - self.0.probability()?)
+    }
+}
+
+/// A closure used as a matcher, e.g.
+/// `Predicate(|candidate: &Candidate| candidate.bytes()[19] == 0)`.
+pub struct Predicate<F>(pub F);
+
+impl<F: Fn(&Candidate) -> bool + Send + Sync> Matcher for Predicate<F> {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        (self.0)(candidate).then_some(0)
+    }
+}
+
+/// Everything a candidate address has to satisfy.
+pub struct Criteria {
+    /// Bit-level constraints every address must meet on top of the patterns.
+    pub required: Option<Pattern>,
+    pub patterns: PatternSet,
+    pub regex: Option<AddressRegex>,
+    pub zero_bytes: Option<ZeroBytes>,
+}
+
+impl Matcher for Criteria {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        if let Some(required) = &self.required {
+            required.evaluate(candidate)?;
+        }
+        if let Some(zero_bytes) = &self.zero_bytes {
+            zero_bytes.evaluate(candidate)?;
+        }
+        self.patterns.evaluate(candidate)?;
+        if let Some(regex) = &self.regex {
+            regex.evaluate(candidate)?;
+        }
+        Some(0)
+    }
+
+    /// Unknown with a regex. The required bits and the zero bytes are
+    /// combined with each pattern, since they may constrain the same
+    /// nibbles, and the patterns add up as in [`PatternSet::probability`].
+    fn probability(&self) -> Option<f64> {
+        if self.regex.is_some() {
+            return None;
+        }
+
+        let patterns = self.patterns.patterns.iter().map(|(_, pattern)| {
+            let mut pattern = pattern.clone();
+            if let Some(required) = &self.required {
+                if pattern.merge(required).is_err() {
+                    return 0.0;
+                }
+            }
+            let zero_bytes = self.zero_bytes.map_or(1.0, |zero_bytes| zero_bytes.probability_given(&pattern));
+            pattern.probability() * zero_bytes
+        });
+
+        Some(patterns.sum::<f64>().min(1.0))
+    }
+}
+
+/// A minimum number of zero bytes, judged on whole bytes rather than hex
+/// characters because only whole zero bytes make calldata cheaper.
+#[derive(Clone, Copy, Debug)]
+pub enum ZeroBytes {
+    /// At least this many zero bytes at the start of the address.
+    Leading(usize),
+    /// At least this many zero bytes anywhere in the address.
+    Anywhere(usize),
+}
+
+impl Matcher for ZeroBytes {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        let matches = match *self {
+            ZeroBytes::Leading(count) => candidate.bytes()[..count].iter().all(|&b| b == 0),
+            ZeroBytes::Anywhere(count) => zero_bytes(candidate.address()) >= count,
+        };
+        matches.then_some(0)
+    }
+
+    fn probability(&self) -> Option<f64> {
+        Some(ZeroBytes::probability(self))
+    }
+}
+
+impl ZeroBytes {
+    /// Exact, since every byte of a random address is zero with probability 1/256.
+    pub fn probability(&self) -> f64 {
+        self.probability_given(&Pattern::default())
+    }
+
+    /// Exact probability that an address matching `pattern` also has the
+    /// zero bytes. Each byte is then zero with probability 1/2 per bit the
+    /// pattern leaves free, or not at all if it fixes a bit to 1.
+    pub fn probability_given(&self, pattern: &Pattern) -> f64 {
+        let zero: [f64; 20] = std::array::from_fn(|i| match pattern.value[i] {
+            0 => 0.5f64.powi(8 - pattern.mask[i].count_ones() as i32),
+            _ => 0.0,
+        });
+
+        match *self {
+            ZeroBytes::Leading(count) => zero[..count].iter().product(),
+            ZeroBytes::Anywhere(count) => {
+                // Distribution of the number of zero bytes, one byte at a time
+                let mut counts = [0.0; 21];
+                counts[0] = 1.0;
+                for (i, p) in zero.iter().enumerate() {
+                    for k in (0..=i + 1).rev() {
+                        let below = if k > 0 { counts[k - 1] } else { 0.0 };
+                        counts[k] = counts[k] * (1.0 - p) + below * p;
+                    }
+                }
+                counts[count..].iter().sum()
+            }
+        }
+    }
+}
+
+pub fn leading_zero_bytes(address: &H160) -> usize {
+    address.as_bytes().iter().take_while(|&&b| b == 0).count()
+}
+
+pub fn zero_bytes(address: &H160) -> usize {
+    address.as_bytes().iter().filter(|&&b| b == 0).count()
+}
+
+/// Whether the first non-zero byte starts with a zero nibble, e.g. `0x0000000f...`.
+/// Such a nibble looks like progress but saves no gas.
+pub fn has_partial_zero_nibble(address: &H160) -> bool {
+    address.as_bytes().iter().find(|&&b| b != 0).is_some_and(|&b| b < 0x10)
+}
+
+/// Address criteria compiled into a 160-bit mask/value pair.
+///
+/// Every constrained nibble of the 40-hex address sets the corresponding bits
+/// of `mask` and `value`, so candidates are checked directly against the raw
+/// 20-byte Keccak output without ever formatting them as strings. Prefixes,
+/// suffixes and positional masks all compile down to the same pair.
+///
+/// In case-sensitive mode the letters of the pattern must additionally have
+/// the same case in the EIP-55 checksum address. That check needs a second
+/// Keccak hash, so it only runs on candidates whose nibbles already match.
+#[derive(Clone, Debug, Default)]
+pub struct Pattern {
+    mask: [u8; 20],
+    value: [u8; 20],
+    /// `(nibble index, uppercase)` for every letter whose case must match.
+    case: Vec<(usize, bool)>,
+}
+
+impl Pattern {
+    pub fn new(prefix: Option<&str>, suffix: Option<&str>, case_sensitive: bool) -> Result<Self, String> {
+        let mut pattern = Pattern::default();
+
+        if let Some(prefix) = prefix {
+            let hex = strip_hex_prefix(prefix);
+            if hex.len() > 40 {
+                return Err("prefix is longer than 40 hex characters".to_string());
+            }
+            pattern.constrain(0, hex, "prefix", case_sensitive)?;
+        }
+
+        if let Some(suffix) = suffix {
+            let hex = strip_hex_prefix(suffix);
+            if hex.len() > 40 {
+                return Err("suffix is longer than 40 hex characters".to_string());
+            }
+            pattern.constrain(40 - hex.len(), hex, "suffix", case_sensitive)?;
+        }
+
+        Ok(pattern)
+    }
+
+    /// Constrains individual bits of the address: every bit set in `mask` must
+    /// equal the same bit of `value`.
+    pub fn from_bits(mask: [u8; 20], value: [u8; 20]) -> Self {
+        let value = std::array::from_fn(|i| value[i] & mask[i]);
+        Pattern { mask, value, case: Vec::new() }
+    }
+
+    /// Parses a positional mask such as `dead????????????????????????????????beef`
+    /// or `0x00..00`. Every character is either a hex nibble or a `?` wildcard,
+    /// and a single `..` stands for as many wildcards as needed to fill the
+    /// 40 characters of the address.
+    pub fn from_mask(mask: &str, case_sensitive: bool) -> Result<Self, String> {
+        let hex = strip_hex_prefix(mask);
+        let expanded = match hex.split_once("..") {
+            Some((head, tail)) => {
+                if tail.contains("..") {
+                    return Err("mask can only contain one `..`".to_string());
+                }
+                if head.len() + tail.len() > 40 {
+                    return Err("mask is longer than 40 hex characters".to_string());
+                }
+                format!("{}{}{}", head, "?".repeat(40 - head.len() - tail.len()), tail)
+            }
+            None => hex.to_string(),
+        };
+
+        if expanded.chars().count() != 40 {
+            return Err("mask must cover all 40 hex characters (use `..` to fill with wildcards)".to_string());
+        }
+
+        let mut pattern = Pattern::default();
+        for (position, c) in expanded.chars().enumerate() {
+            if c != '?' {
+                pattern.set_nibble(position, c, "mask", case_sensitive)?;
+            }
+        }
+
+        Ok(pattern)
+    }
+
+    /// Parses one line of a patterns file. Lines use the `--mask` syntax, and a
+    /// line shorter than 40 characters without `..` is taken as a prefix.
+    pub fn parse(line: &str, case_sensitive: bool) -> Result<Self, String> {
+        let hex = strip_hex_prefix(line);
+        if hex.contains("..") || hex.chars().count() >= 40 {
+            Pattern::from_mask(hex, case_sensitive)
+        } else {
+            Pattern::from_mask(&format!("{}..", hex), case_sensitive)
+        }
+    }
+
+    /// Adds the constraints of `other`, failing if the two contradict each other.
+    pub fn merge(&mut self, other: &Pattern) -> Result<(), String> {
+        for byte in 0..20 {
+            let overlap = self.mask[byte] & other.mask[byte];
+            if self.value[byte] & overlap != other.value[byte] & overlap {
+                return Err(format!("conflicting characters around position {}", byte * 2));
+            }
+            self.mask[byte] |= other.mask[byte];
+            self.value[byte] |= other.value[byte];
+        }
+
+        for &(position, uppercase) in &other.case {
+            self.set_case(position, uppercase)?;
+        }
+
+        Ok(())
+    }
+
+    /// Exact probability that a random address matches: one half for every
+    /// constrained bit and for every letter whose checksum case is fixed.
+    pub fn probability(&self) -> f64 {
+        let bits: u32 = self.mask.iter().map(|byte| byte.count_ones()).sum();
+        0.5f64.powi((bits as usize + self.case.len()) as i32)
+    }
+
+    /// Fixed nibble at `position`, if that position is constrained.
+    fn nibble(&self, position: usize) -> Option<u8> {
+        let shift = if position % 2 == 1 { 0 } else { 4 };
+        let byte = position / 2;
+        (self.mask[byte] >> shift & 0xf == 0xf).then_some(self.value[byte] >> shift & 0xf)
+    }
+
+    /// Constrains the nibbles starting at nibble `offset` to the characters of `hex`.
+    fn constrain(&mut self, offset: usize, hex: &str, name: &str, case_sensitive: bool) -> Result<(), String> {
+        for (i, c) in hex.chars().enumerate() {
+            self.set_nibble(offset + i, c, name, case_sensitive)?;
+        }
+        Ok(())
+    }
+
+    fn set_nibble(&mut self, position: usize, c: char, name: &str, case_sensitive: bool) -> Result<(), String> {
+        let nibble = c
+            .to_digit(16)
+            .ok_or_else(|| format!("{} contains non-hex character '{}'", name, c))? as u8;
+        let shift = if position % 2 == 1 { 0 } else { 4 };
+        let byte = position / 2;
+
+        if self.mask[byte] & (0xf << shift) != 0 && (self.value[byte] >> shift) & 0xf != nibble {
+            return Err(format!("conflicting characters at position {}", position));
+        }
+        self.mask[byte] |= 0xf << shift;
+        self.value[byte] |= nibble << shift;
+
+        if case_sensitive && c.is_ascii_alphabetic() {
+            self.set_case(position, c.is_ascii_uppercase())?;
+        }
+
+        Ok(())
+    }
+
+    fn set_case(&mut self, position: usize, uppercase: bool) -> Result<(), String> {
+        match self.case.iter().find(|&&(p, _)| p == position) {
+            Some(&(_, existing)) if existing != uppercase => {
+                Err(format!("conflicting letter case at position {}", position))
+            }
+            Some(_) => Ok(()),
+            None => {
+                self.case.push((position, uppercase));
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Matcher for Pattern {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        let nibbles_match = candidate
+            .bytes()
+            .iter()
+            .zip(&self.mask)
+            .zip(&self.value)
+            .all(|((byte, mask), value)| byte & mask == *value);
+        if !nibbles_match {
+            return None;
+        }
+
+        if self.case.is_empty() {
+            return Some(0);
+        }
+
+        let hash = candidate.checksum_hash();
+        self.case
+            .iter()
+            .all(|&(position, uppercase)| is_uppercase(hash, position) == uppercase)
+            .then_some(0)
+    }
+
+    fn probability(&self) -> Option<f64> {
+        Some(Pattern::probability(self))
+    }
+}
+
+fn strip_hex_prefix(input: &str) -> &str {
+    input.strip_prefix("0x").unwrap_or(input)
+}
+
+/// Nibble `position` (0 to 39) of an address.
+fn address_nibble(bytes: &[u8; 20], position: usize) -> u8 {
+    if position % 2 == 1 {
+        bytes[position / 2] & 0xf
+    } else {
+        bytes[position / 2] >> 4
+    }
+}
+
+/// Any number of patterns, matched against every candidate in a single pass.
+///
+/// Patterns are indexed in two nibble tries: one keyed on their fixed leading
+/// nibbles and one on their fixed trailing nibbles, whichever run is longer.
+/// A candidate is then only compared in full against the patterns found along
+/// its own path through each trie, which is a handful at most even for
+/// hundreds of patterns. Patterns anchored at neither end are checked linearly.
+#[derive(Default)]
+pub struct PatternSet {
+    patterns: Vec<(String, Pattern)>,
+    prefixes: NibbleTrie,
+    suffixes: NibbleTrie,
+    unanchored: Vec<usize>,
+}
+
+impl PatternSet {
+    /// Loads one pattern per line, skipping blank lines and `#` comments.
+    pub fn from_file(path: &str, case_sensitive: bool) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|err| format!("cannot read {}: {}", path, err))?;
+
+        let mut set = PatternSet::default();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let pattern = Pattern::parse(line, case_sensitive)
+                .map_err(|err| format!("{} line {}: {}", path, number + 1, err))?;
+            set.add(line.to_string(), pattern);
+        }
+
+        if set.is_empty() {
+            return Err(format!("{} contains no patterns", path));
+        }
+
+        Ok(set)
+    }
+
+    pub fn add(&mut self, label: String, pattern: Pattern) {
+        let index = self.patterns.len();
+        let leading: Vec<u8> = (0..40).map_while(|p| pattern.nibble(p)).collect();
+        let trailing: Vec<u8> = (0..40).rev().map_while(|p| pattern.nibble(p)).collect();
+
+        if leading.is_empty() && trailing.is_empty() {
+            self.unanchored.push(index);
+        } else if leading.len() >= trailing.len() {
+            self.prefixes.insert(&leading, index);
+        } else {
+            self.suffixes.insert(&trailing, index);
+        }
+
+        self.patterns.push((label, pattern));
+    }
+
+    pub fn len(&self) -> usize {
+        self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    pub fn label(&self, index: usize) -> &str {
+        &self.patterns[index].0
+    }
+
+    /// Probability that a random address matches any of the patterns. Exact
+    /// when no two patterns can match the same address (e.g. distinct
+    /// prefixes of equal length), an overestimate otherwise.
+    pub fn probability(&self) -> f64 {
+        self.patterns.iter().map(|(_, pattern)| pattern.probability()).sum::<f64>().min(1.0)
+    }
+
+    /// Index of the first pattern the candidate satisfies.
+    pub fn find(&self, candidate: &Candidate) -> Option<usize> {
+        let bytes = candidate.bytes();
+        let check = |index: usize| self.patterns[index].1.matches(candidate);
+
+        self.prefixes
+            .find((0..40).map(|p| address_nibble(bytes, p)), check)
+            .or_else(|| self.suffixes.find((0..40).rev().map(|p| address_nibble(bytes, p)), check))
+            .or_else(|| self.unanchored.iter().copied().find(|&index| check(index)))
+    }
+}
+
+/// Matches when any of the patterns does.
+impl Matcher for PatternSet {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        self.find(candidate).map(|_| 0)
+    }
+
+    fn probability(&self) -> Option<f64> {
+        Some(PatternSet::probability(self))
+    }
+}
+
+/// A trie over address nibbles, holding pattern indexes at the node where
+/// their fixed run of nibbles ends.
+#[derive(Default)]
+struct NibbleTrie {
+    nodes: Vec<TrieNode>,
+}
+
+#[derive(Default)]
+struct TrieNode {
+    /// Index of the child node for each nibble, 0 if there is none.
+    children: [u32; 16],
+    patterns: Vec<usize>,
+}
+
+impl NibbleTrie {
+    fn insert(&mut self, nibbles: &[u8], pattern: usize) {
+        if self.nodes.is_empty() {
+            self.nodes.push(TrieNode::default());
+        }
+
+        let mut node = 0;
+        for &nibble in nibbles {
+            let child = self.nodes[node].children[nibble as usize];
+            node = if child == 0 {
+                self.nodes.push(TrieNode::default());
+                let child = self.nodes.len() - 1;
+                self.nodes[node].children[nibble as usize] = child as u32;
+                child
+            } else {
+                child as usize
+            };
+        }
+
+        self.nodes[node].patterns.push(pattern);
+    }
+
+    /// Walks the trie along `nibbles`, returning the first pattern on the way
+    /// for which `check` holds.
+    fn find(&self, nibbles: impl Iterator<Item = u8>, check: impl Fn(usize) -> bool) -> Option<usize> {
+        if self.nodes.is_empty() {
+            return None;
+        }
+
+        let mut node = &self.nodes[0];
+        for nibble in nibbles {
+            let child = node.children[nibble as usize];
+            if child == 0 {
+                break;
+            }
+            node = &self.nodes[child as usize];
+            if let Some(&pattern) = node.patterns.iter().find(|&&pattern| check(pattern)) {
+                return Some(pattern);
+            }
+        }
+
+        None
+    }
+}
+
+/// A regular expression evaluated against the 40 hex characters of an address.
+///
+/// Without `case_sensitive` the regex ignores case; otherwise it sees the
+/// EIP-55 checksum form. Fixed leading (`^...`) and trailing (`...$`) hex
+/// literals are compiled into a [`Pattern`] so that the regex only runs on
+/// candidates that already share them.
+pub struct AddressRegex {
+    prefilter: Pattern,
+    regex: Regex,
+    case_sensitive: bool,
+}
+
+impl AddressRegex {
+    pub fn new(pattern: &str, case_sensitive: bool) -> Result<Self, String> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map_err(|err| format!("invalid regex: {}", err))?;
+
+        let (prefix, suffix) = regex_literals(pattern);
+        // A literal that doesn't fit an address can never match; leave that to the regex
+        let prefilter = Pattern::new(prefix.as_deref(), suffix.as_deref(), false)
+            .or_else(|_| Pattern::new(None, None, false))?;
+
+        Ok(AddressRegex { prefilter, regex, case_sensitive })
+    }
+}
+
+impl Matcher for AddressRegex {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        self.prefilter.evaluate(candidate)?;
+
+        let mut lowercase = [0u8; 40];
+        let hex = if self.case_sensitive {
+            candidate.checksum()
+        } else {
+            hex::encode_to_slice(candidate.bytes(), &mut lowercase).unwrap();
+            std::str::from_utf8(&lowercase).unwrap()
+        };
+
+        self.regex.is_match(hex).unwrap_or(false).then_some(0)
+    }
+}
+
+/// Extracts the hex literal anchored at the start (`^...`) and at the end
+/// (`...$`) of a regex. This is deliberately conservative: any alternation
+/// disables it, and a literal stops at the first character that isn't a
+/// plain hex digit, dropping a digit that a quantifier applies to.
+fn regex_literals(pattern: &str) -> (Option<String>, Option<String>) {
+    if pattern.contains('|') {
+        return (None, None);
+    }
+
+    let is_quantifier = |c: char| matches!(c, '?' | '*' | '+' | '{');
+    let chars: Vec<char> = pattern.chars().collect();
+
+    let prefix = pattern.strip_prefix('^').map(|rest| {
+        let mut literal: Vec<char> = rest.chars().take_while(|c| c.is_ascii_hexdigit()).collect();
+        if rest.chars().nth(literal.len()).is_some_and(is_quantifier) {
+            literal.pop();
+        }
+        literal.into_iter().collect::<String>()
+    });
+
+    let anchored_end = chars.len() >= 2 && chars[chars.len() - 1] == '$' && chars[chars.len() - 2] != '\\';
+    let suffix = anchored_end.then(|| {
+        let body = &chars[..chars.len() - 1];
+        // Escapes such as `\d`, `\12` or `\x41` aren't literals, so the
+        // literal can only start after the last of them
+        let mut literal_start = 0;
+        let mut i = 0;
+        while i < body.len() {
+            if body[i] != '\\' {
+                i += 1;
+                continue;
+            }
+            i += 2;
+            let (max_digits, is_digit): (usize, fn(&char) -> bool) = match body.get(i - 1) {
+                Some('x') => (2, char::is_ascii_hexdigit),
+                Some('u') => (4, char::is_ascii_hexdigit),
+                Some('U') => (8, char::is_ascii_hexdigit),
+                Some(c) if c.is_ascii_digit() => (usize::MAX, char::is_ascii_digit),
+                _ => (0, char::is_ascii_digit),
+            };
+            let digits = body[i.min(body.len())..].iter().take(max_digits).take_while(|c| is_digit(c)).count();
+            i += digits;
+            literal_start = i;
+        }
+
+        let mut start = body.len();
+        while start > literal_start && body[start - 1].is_ascii_hexdigit() {
+            start -= 1;
+        }
+        body[start..].iter().collect::<String>()
+    });
+
+    let non_empty = |literal: Option<String>| literal.filter(|l| !l.is_empty());
+    (non_empty(prefix), non_empty(suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn criteria(pattern: Pattern, zero_bytes: Option<ZeroBytes>) -> Criteria {
+        let mut patterns = PatternSet::default();
+        patterns.add(String::new(), pattern);
+        Criteria { required: None, patterns, regex: None, zero_bytes }
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual / expected - 1.0).abs() < 1e-9, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn zero_bytes_overlapping_a_prefix_are_not_counted_twice() {
+        let prefix = Pattern::new(Some("0000"), None, false).unwrap();
+        let criteria = criteria(prefix, Some(ZeroBytes::Leading(2)));
+        assert_close(criteria.probability().unwrap(), 1.0 / 65536.0);
+    }
+
+    #[test]
+    fn zero_bytes_contradicting_a_prefix_are_impossible() {
+        let prefix = Pattern::new(Some("01"), None, false).unwrap();
+        assert_eq!(criteria(prefix.clone(), Some(ZeroBytes::Leading(1))).probability(), Some(0.0));
+        assert_close(
+            criteria(prefix, Some(ZeroBytes::Anywhere(1))).probability().unwrap(),
+            1.0 / 256.0 * (1.0 - (255.0f64 / 256.0).powi(19)),
+        );
+    }
+
+    #[test]
+    fn zero_bytes_anywhere_follow_the_binomial() {
+        assert_close(ZeroBytes::Anywhere(1).probability(), 1.0 - (255.0f64 / 256.0).powi(20));
+        assert_close(ZeroBytes::Anywhere(20).probability(), 256.0f64.powi(-20));
+        assert_close(ZeroBytes::Leading(3).probability(), 256.0f64.powi(-3));
+    }
+
+    fn some(literal: &str) -> Option<String> {
+        Some(literal.to_string())
+    }
+
+    #[test]
+    fn regex_literals_drop_quantified_digits() {
+        assert_eq!(regex_literals("^abc"), (some("abc"), None));
+        assert_eq!(regex_literals("^ab{2}"), (some("a"), None));
+        assert_eq!(regex_literals("^ab+"), (some("a"), None));
+        assert_eq!(regex_literals("^ab?"), (some("a"), None));
+        assert_eq!(regex_literals("^a*"), (None, None));
+        assert_eq!(regex_literals("^ab[0-9]"), (some("ab"), None));
+        assert_eq!(regex_literals("^dead.*beef$"), (some("dead"), some("beef")));
+        assert_eq!(regex_literals("ab?$"), (None, None));
+    }
+
+    #[test]
+    fn regex_literals_skip_escapes() {
+        assert_eq!(regex_literals(r"\d{2}ab$"), (None, some("ab")));
+        assert_eq!(regex_literals(r"\x41$"), (None, None));
+        assert_eq!(regex_literals(r"\x41b$"), (None, some("b")));
+        assert_eq!(regex_literals(r"(a)\1$"), (None, None));
+        assert_eq!(regex_literals(r"\12$"), (None, None));
+        // An escaped backslash is a literal, so the digits after it are too
+        assert_eq!(regex_literals(r"\\12$"), (None, some("12")));
+        assert_eq!(regex_literals(r"ab\$"), (None, None));
+        assert_eq!(regex_literals(r"^\x41"), (None, None));
+    }
+
+    #[test]
+    fn regex_literals_give_up_on_alternation() {
+        assert_eq!(regex_literals("^ab|cd$"), (None, None));
+        assert_eq!(regex_literals("^(ab|cd)ef$"), (None, None));
+        assert_eq!(regex_literals("^a(b|c)"), (None, None));
+    }
+
+    /// Addresses built from heads and tails the regexes below care about,
+    /// with random nibbles in between.
+    fn sample_addresses() -> Vec<H160> {
+        let heads = ["", "a", "ab", "abb", "abbb", "aab", "12", "dead"];
+        let tails = ["", "a", "ab", "0ab", "1ab", "12", "41", "beef"];
+        let mut addresses = Vec::new();
+        for head in heads {
+            for tail in tails {
+                let middle = hex::encode(H160::random());
+                let hex = format!("{}{}{}", head, &middle[..40 - head.len() - tail.len()], tail);
+                addresses.push(hex.parse().unwrap());
+            }
+        }
+        addresses
+    }
+
+    #[test]
+    fn address_regex_agrees_with_the_bare_regex() {
+        let patterns = [
+            "^ab{2}", "^ab+", "^ab?c", r"\d{2}ab$", r"\x41$", r"\\12$", "^ab|12$", "^(a|12)", "^dead.*beef$", "1?ab$",
+        ];
+        let addresses = sample_addresses();
+        for pattern in patterns {
+            let address_regex = AddressRegex::new(pattern, false).unwrap();
+            let regex = RegexBuilder::new(pattern).case_insensitive(true).build().unwrap();
+            let mut matched = 0;
+            for address in &addresses {
+                let expected = regex.is_match(&hex::encode(address)).unwrap();
+                assert_eq!(address_regex.matches(&Candidate::new(address)), expected, "{} on {:x}", pattern, address);
+                matched += expected as usize;
+            }
+            assert!(matched > 0 || pattern == r"\\12$", "{} matched no sample", pattern);
+        }
+    }
+
+    fn address(hex: &str) -> H160 {
+        hex.parse().unwrap()
+    }
+
+    fn matches(pattern: &Pattern, hex: &str) -> bool {
+        pattern.matches(&Candidate::new(&address(hex)))
+    }
+
+    #[test]
+    fn prefix_and_suffix_compile_to_nibble_masks() {
+        let pattern = Pattern::new(Some("0xabc"), Some("d"), false).unwrap();
+        assert_eq!(pattern.mask[..2], [0xff, 0xf0]);
+        assert_eq!(pattern.value[..2], [0xab, 0xc0]);
+        assert_eq!(pattern.mask[2..19], [0; 17]);
+        assert_eq!((pattern.mask[19], pattern.value[19]), (0x0f, 0x0d));
+        assert_eq!(pattern.probability(), 0.5f64.powi(16));
+
+        assert!(matches(&pattern, "abc000000000000000000000000000000000000d"));
+        assert!(matches(&pattern, "abcfffffffffffffffffffffffffffffffffffed"));
+        assert!(!matches(&pattern, "abd000000000000000000000000000000000000d"));
+        assert!(!matches(&pattern, "abc00000000000000000000000000000000000d0"));
+    }
+
+    #[test]
+    fn masks_constrain_odd_positions() {
+        let pattern = Pattern::from_mask("?a??????????????????????????????????????", false).unwrap();
+        assert_eq!((pattern.mask[0], pattern.value[0]), (0x0f, 0x0a));
+        assert!(matches(&pattern, "fa00000000000000000000000000000000000000"));
+        assert!(!matches(&pattern, "af00000000000000000000000000000000000000"));
+        assert_eq!(pattern.nibble(1), Some(0xa));
+        assert_eq!(pattern.nibble(0), None);
+    }
+
+    #[test]
+    fn pattern_errors() {
+        assert!(Pattern::new(Some(&"a".repeat(41)), None, false).is_err());
+        assert!(Pattern::new(Some("ag"), None, false).is_err());
+        assert!(Pattern::new(Some(&"a".repeat(40)), Some("b"), false).is_err());
+        assert!(Pattern::new(Some(&"a".repeat(40)), Some("a"), false).is_ok());
+        assert!(Pattern::new(Some("ab"), Some(&"a".repeat(39)), false).is_err());
+    }
+
+    #[test]
+    fn merge_rejects_conflicts() {
+        let mut pattern = Pattern::new(Some("ab"), None, false).unwrap();
+        assert!(pattern.merge(&Pattern::new(Some("a"), Some("cd"), false).unwrap()).is_ok());
+        assert!(matches(&pattern, "ab000000000000000000000000000000000000cd"));
+        assert_eq!(pattern.probability(), 0.5f64.powi(16));
+
+        assert!(pattern.merge(&Pattern::new(Some("ac"), None, false).unwrap()).is_err());
+        // Conflicts are per bit, so disjoint bits of the same byte merge
+        let mut bits = Pattern::from_bits([0x80; 20], [0x80; 20]);
+        assert!(bits.merge(&Pattern::from_bits([0x01; 20], [0x00; 20])).is_ok());
+        assert!(bits.merge(&Pattern::from_bits([0x80; 20], [0x00; 20])).is_err());
+
+        let mut upper = Pattern::new(Some("A"), None, true).unwrap();
+        assert!(upper.merge(&Pattern::new(Some("A"), None, true).unwrap()).is_ok());
+        assert!(upper.merge(&Pattern::new(Some("a"), None, true).unwrap()).is_err());
+    }
+
+    #[test]
+    fn double_dot_fills_with_wildcards() {
+        let mask = Pattern::from_mask("0x00..00", false).unwrap();
+        let explicit = Pattern::new(Some("00"), Some("00"), false).unwrap();
+        assert_eq!((mask.mask, mask.value), (explicit.mask, explicit.value));
+
+        assert!(Pattern::from_mask("..", false).unwrap().mask.iter().all(|&byte| byte == 0));
+        assert!(Pattern::from_mask(&"0".repeat(40), false).is_ok());
+        assert!(Pattern::from_mask(&format!("{}..", "0".repeat(40)), false).is_ok());
+        assert!(Pattern::from_mask(&format!("{}..0", "0".repeat(40)), false).is_err());
+        assert!(Pattern::from_mask("ab..cd..ef", false).is_err());
+        assert!(Pattern::from_mask("abcd", false).is_err());
+
+        let prefix = Pattern::parse("dead", false).unwrap();
+        assert_eq!(prefix.mask, Pattern::new(Some("dead"), None, false).unwrap().mask);
+        let suffix = Pattern::parse("0x..beef", false).unwrap();
+        assert_eq!(suffix.value, Pattern::new(None, Some("beef"), false).unwrap().value);
+        assert!(Pattern::parse(&"1".repeat(40), false).is_ok());
+    }
+
+    /// The mixed-case examples of EIP-55.
+    #[test]
+    fn case_sensitive_patterns_follow_eip55() {
+        for checksummed in [
+            "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "fB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "dbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "D1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            assert_eq!(Candidate::new(&address(checksummed)).checksum(), checksummed);
+            assert!(matches(&Pattern::from_mask(checksummed, true).unwrap(), checksummed));
+            assert!(matches(&Pattern::from_mask(checksummed, false).unwrap(), checksummed));
+            let swapped: String = checksummed
+                .chars()
+                .map(|c| if c.is_ascii_uppercase() { c.to_ascii_lowercase() } else { c.to_ascii_uppercase() })
+                .collect();
+            assert!(!matches(&Pattern::from_mask(&swapped, true).unwrap(), checksummed));
+            assert!(matches(&Pattern::from_mask(&swapped, false).unwrap(), checksummed));
+        }
+
+        let address = "5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        assert!(matches(&Pattern::new(Some("5aAe"), Some("BeAed"), true).unwrap(), address));
+        assert!(!matches(&Pattern::new(Some("5AAe"), None, true).unwrap(), address));
+        assert!(!matches(&Pattern::new(None, Some("beaed"), true).unwrap(), address));
+        // Digits have no case, so only the letters count towards the odds
+        assert_eq!(Pattern::new(Some("5aAe"), None, true).unwrap().probability(), 0.5f64.powi(16 + 3));
+    }
+
+    fn pattern_set(lines: &[&str]) -> PatternSet {
+        let mut set = PatternSet::default();
+        for line in lines {
+            set.add(line.to_string(), Pattern::parse(line, false).unwrap());
+        }
+        set
+    }
+
+    #[test]
+    fn pattern_set_indexes_by_the_longer_anchor() {
+        let set = pattern_set(&["ab", "abc", "abcd..00", "..beef", "a..cafe", "????dd..", "..ee??"]);
+        assert_eq!(set.prefixes.nodes.len(), 5); // root, a, ab, abc, abcd
+        assert_eq!(set.suffixes.nodes.len(), 9); // root, the 4 nibbles of beef and of cafe
+        assert_eq!(set.unanchored, [5, 6]);
+
+        let find = |hex: &str| set.find(&Candidate::new(&address(hex))).map(|index| set.label(index));
+        assert_eq!(find("ab00000000000000000000000000000000000000"), Some("ab"));
+        // Nested prefixes report the shortest one along the path
+        assert_eq!(find("abcd000000000000000000000000000000000000"), Some("ab"));
+        assert_eq!(find("a000000000000000000000000000000000000000"), None);
+        assert_eq!(find("000000000000000000000000000000000000beef"), Some("..beef"));
+        assert_eq!(find("a00000000000000000000000000000000000cafe"), Some("a..cafe"));
+        assert_eq!(find("b00000000000000000000000000000000000cafe"), None);
+        assert_eq!(find("0000dd0000000000000000000000000000000000"), Some("????dd.."));
+        assert_eq!(find("000000000000000000000000000000000000ee00"), Some("..ee??"));
+        assert_eq!(find("0000000000000000000000000000000000000000"), None);
+    }
+
+    #[test]
+    fn pattern_set_checks_the_whole_pattern_after_the_trie() {
+        let set = pattern_set(&["abcd..00", "ab..ff"]);
+        let find = |hex: &str| set.find(&Candidate::new(&address(hex))).map(|index| set.label(index));
+        assert_eq!(find("abcd000000000000000000000000000000000000"), Some("abcd..00"));
+        assert_eq!(find("abcd0000000000000000000000000000000000ff"), Some("ab..ff"));
+        assert_eq!(find("abcd000000000000000000000000000000000011"), None);
+        assert_eq!(set.probability(), 0.5f64.powi(24) + 0.5f64.powi(16));
+    }
+}
@@ -0,0 +1,130 @@
+use crate::hd::ExtendedPrivateKey;
+use crate::search::{public_key_address, Secret, Worker};
+use bip39::Mnemonic;
+use ethereum_types::H160;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Checks that `words` is one of the BIP-39 lengths: 12, 15, 18, 21 or 24.
+pub fn check_word_count(words: usize) -> Result<(), String> {
+    match words {
+        12 | 15 | 18 | 21 | 24 => Ok(()),
+        _ => Err("word count must be 12, 15, 18, 21 or 24".to_string()),
+    }
+}
+
+/// Generates random BIP-39 mnemonics and derives the account at a fixed path.
+///
+/// Every candidate needs a fresh seed, i.e. 2048 rounds of PBKDF2-HMAC-SHA512,
+/// so there is nothing to share between candidates and each batch is a
+/// single mnemonic.
+pub struct MnemonicMiner {
+    secp: Secp256k1<secp256k1::All>,
+    path: Vec<u32>,
+    entropy: Vec<u8>,
+    mnemonic: Option<Mnemonic>,
+    private_key: Option<SecretKey>,
+    addresses: Vec<H160>,
+}
+
+impl MnemonicMiner {
+    /// Fails on the word counts [`check_word_count`] rejects.
+    pub fn new(words: usize, path: &[u32]) -> Result<Self, String> {
+        check_word_count(words)?;
+        Ok(MnemonicMiner {
+            secp: Secp256k1::new(),
+            path: path.to_vec(),
+            entropy: vec![0; words * 4 / 3],
+            mnemonic: None,
+            private_key: None,
+            addresses: Vec::with_capacity(1),
+        })
+    }
+
+    /// Derives the account of `mnemonic` as the current candidate.
+    fn derive(&mut self, mnemonic: Mnemonic) -> bool {
+        let seed = Zeroizing::new(mnemonic.to_seed(""));
+
+        let Some(account) = ExtendedPrivateKey::master(&*seed).and_then(|master| master.derive(&self.secp, &self.path))
+        else {
+            return false;
+        };
+
+        self.addresses.clear();
+        self.addresses.push(public_key_address(&PublicKey::from_secret_key(&self.secp, &account.key)));
+        self.mnemonic = Some(mnemonic);
+        self.private_key = Some(account.key);
+        true
+    }
+}
+
+impl Drop for MnemonicMiner {
+    fn drop(&mut self) {
+        self.entropy.zeroize();
+        if let Some(private_key) = &mut self.private_key {
+            private_key.non_secure_erase();
+        }
+    }
+}
+
+impl Worker for MnemonicMiner {
+    /// Returns `false` in the astronomically unlikely case that the path
+    /// derives to an invalid key.
+    fn next_batch(&mut self) -> bool {
+        OsRng.fill_bytes(&mut self.entropy);
+        let mnemonic = Mnemonic::from_entropy(&self.entropy).expect("entropy has a BIP-39 length");
+        self.derive(mnemonic)
+    }
+
+    fn addresses(&self) -> &[H160] {
+        &self.addresses
+    }
+
+    fn secret(&self, _index: usize) -> Secret {
+        Secret::Mnemonic {
+            phrase: self.mnemonic.as_ref().unwrap().to_string(),
+            private_key: self.private_key.unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hd::{parse_path, DEFAULT_PATH};
+
+    /// The all-zero entropy mnemonic, whose first account every wallet
+    /// reports as 0x9858EfFD232B4033E47d90003D41EC34EcaEda94.
+    #[test]
+    fn abandon_about_test_vector() {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mut miner = MnemonicMiner::new(12, &parse_path(DEFAULT_PATH).unwrap()).unwrap();
+        assert!(miner.derive(Mnemonic::parse(phrase).unwrap()));
+        assert_eq!(miner.addresses(), ["9858effd232b4033e47d90003d41ec34ecaeda94".parse().unwrap()]);
+
+        let secret = miner.secret(0);
+        let Secret::Mnemonic { phrase: reported, private_key } = &secret else {
+            panic!("MnemonicMiner reports mnemonics");
+        };
+        assert_eq!(reported, phrase);
+        assert_eq!(
+            hex::encode(private_key.secret_bytes()),
+            "1ab42cc412b618bdea3a599e3c9bae199ebf030895b039e9db1e30dafb12b727"
+        );
+    }
+
+    #[test]
+    fn new_checks_the_word_count() {
+        let path = parse_path(DEFAULT_PATH).unwrap();
+        for words in [12, 15, 18, 21, 24] {
+            let mut miner = MnemonicMiner::new(words, &path).unwrap();
+            assert!(miner.next_batch());
+            assert_eq!(miner.mnemonic.as_ref().unwrap().word_count(), words);
+        }
+        for words in [0, 11, 13, 25, 48] {
+            assert!(MnemonicMiner::new(words, &path).is_err(), "{} words", words);
+        }
+    }
+}
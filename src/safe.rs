@@ -0,0 +1,239 @@
+use crate::abi::{address_word, uint_word};
+use crate::search::{Secret, Worker};
+use ethereum_types::H160;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use sha3::{Digest, Keccak256};
+
+/// Selector of `setup(address[],uint256,address,bytes,address,address,uint256,address)`.
+const SETUP_SELECTOR: [u8; 4] = [0xb6, 0x3e, 0x80, 0x0d];
+
+/// Calldata of `Safe.setup` as the proxy factory receives it as `initializer`:
+/// the given owners, threshold and fallback handler, with no delegate call
+/// (`to = 0`, empty `data`) and no refund payment.
+pub fn setup_calldata(owners: &[H160], threshold: usize, fallback_handler: &H160) -> Vec<u8> {
+    let head_size = 8 * 32;
+    let owners_offset = head_size;
+    let data_offset = owners_offset + 32 * (1 + owners.len());
+
+    let mut calldata = SETUP_SELECTOR.to_vec();
+    calldata.extend(uint_word(owners_offset));
+    calldata.extend(uint_word(threshold));
+    calldata.extend(address_word(&H160::zero())); // to
+    calldata.extend(uint_word(data_offset));
+    calldata.extend(address_word(fallback_handler));
+    calldata.extend(address_word(&H160::zero())); // paymentToken
+    calldata.extend(uint_word(0)); // payment
+    calldata.extend(address_word(&H160::zero())); // paymentReceiver
+
+    calldata.extend(uint_word(owners.len()));
+    for owner in owners {
+        calldata.extend(address_word(owner));
+    }
+    calldata.extend(uint_word(0)); // data.length
+
+    calldata
+}
+
+/// Checks the owners the way `Safe.setup` does, which reverts on the zero
+/// address and the owner list's `0x…01` sentinel (GS203) and on duplicates
+/// (GS204). A Safe with such owners can never be deployed.
+pub fn validate_owners(owners: &[H160]) -> Result<(), String> {
+    let sentinel = H160::from_low_u64_be(1);
+    for (i, owner) in owners.iter().enumerate() {
+        if owner.is_zero() || *owner == sentinel {
+            return Err(format!("owner 0x{:x} is not allowed by Safe.setup", owner));
+        }
+        if owners[..i].contains(owner) {
+            return Err(format!("owner 0x{:x} is listed twice", owner));
+        }
+    }
+    Ok(())
+}
+
+/// Mines the `saltNonce` of `createProxyWithNonce` on a Safe proxy factory.
+///
+/// The factory deploys with CREATE2 using
+/// `salt = keccak256(keccak256(initializer) ++ saltNonce)` and init code
+/// `proxyCreationCode ++ uint256(singleton)`, so every candidate costs two
+/// Keccak hashes. Each worker counts up from a random `saltNonce`.
+pub struct SafeMiner {
+    /// `keccak256(initializer) ++ saltNonce`
+    salt_preimage: [u8; 64],
+    /// `0xff ++ factory ++ salt ++ keccak256(proxyCreationCode ++ uint256(singleton))`
+    address_preimage: [u8; 85],
+    /// Value of the salt nonce's last 8 bytes for the first address of the batch.
+    counter: u64,
+    batch_size: usize,
+    addresses: Vec<H160>,
+}
+
+impl SafeMiner {
+    pub fn random(
+        factory: &H160,
+        singleton: &H160,
+        proxy_creation_code: &[u8],
+        initializer: &[u8],
+        batch_size: usize,
+    ) -> Self {
+        let mut salt_nonce = [0u8; 32];
+        OsRng.fill_bytes(&mut salt_nonce);
+        Self::new(factory, singleton, proxy_creation_code, initializer, salt_nonce, batch_size)
+    }
+
+    /// Starts counting at `salt_nonce`.
+    pub fn new(
+        factory: &H160,
+        singleton: &H160,
+        proxy_creation_code: &[u8],
+        initializer: &[u8],
+        salt_nonce: [u8; 32],
+        batch_size: usize,
+    ) -> Self {
+        let mut salt_preimage = [0u8; 64];
+        salt_preimage[..32].copy_from_slice(&Keccak256::digest(initializer));
+        salt_preimage[32..].copy_from_slice(&salt_nonce);
+
+        let mut deployment_data = proxy_creation_code.to_vec();
+        deployment_data.extend(address_word(singleton));
+
+        let mut address_preimage = [0u8; 85];
+        address_preimage[0] = 0xff;
+        address_preimage[1..21].copy_from_slice(factory.as_bytes());
+        address_preimage[53..85].copy_from_slice(&Keccak256::digest(deployment_data));
+
+        SafeMiner {
+            counter: u64::from_be_bytes(salt_preimage[56..].try_into().unwrap()),
+            salt_preimage,
+            address_preimage,
+            batch_size,
+            addresses: Vec::with_capacity(batch_size),
+        }
+    }
+
+    /// Salt nonce behind `addresses()[index]`, as a big-endian uint256.
+    pub fn salt_nonce(&self, index: usize) -> [u8; 32] {
+        let mut salt_nonce: [u8; 32] = self.salt_preimage[32..].try_into().unwrap();
+        salt_nonce[24..].copy_from_slice(&self.counter.wrapping_add(index as u64).to_be_bytes());
+        salt_nonce
+    }
+}
+
+impl Worker for SafeMiner {
+    fn next_batch(&mut self) -> bool {
+        self.counter = self.counter.wrapping_add(self.addresses.len() as u64);
+        self.addresses.clear();
+
+        for i in 0..self.batch_size {
+            let counter = self.counter.wrapping_add(i as u64);
+            self.salt_preimage[56..].copy_from_slice(&counter.to_be_bytes());
+            let salt = Keccak256::digest(self.salt_preimage);
+            self.address_preimage[21..53].copy_from_slice(&salt);
+            self.addresses.push(H160::from_slice(&Keccak256::digest(self.address_preimage)[12..]));
+        }
+
+        true
+    }
+
+    fn addresses(&self) -> &[H160] {
+        &self.addresses
+    }
+
+    fn secret(&self, index: usize) -> Secret {
+        Secret::SaltNonce(self.salt_nonce(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Safe v1.3.0 deployment addresses. No node was reachable to replay a
+    // real deployment, so the expected values below come from a separate
+    // Python implementation of Keccak-256 and the ABI encoding, which
+    // reproduces the EIP-1014 test vectors.
+    const FACTORY: &str = "a6b71e26c5e0845f74c812102ca7114b6a896ab2";
+    const SINGLETON: &str = "d9db270c1b5e3bd161e8c8503c55ceabee709552";
+    const FALLBACK_HANDLER: &str = "f48f2b2d2a534e402487b3ee7c18c33aec0fe5e4";
+    const PROXY_CREATION_CODE: &str = "608060405234801561001057600080fd5b50";
+
+    fn owners() -> Vec<H160> {
+        vec![H160::repeat_byte(0x11), H160::repeat_byte(0x22)]
+    }
+
+    fn miner(salt_nonce: [u8; 32], batch_size: usize) -> SafeMiner {
+        let initializer = setup_calldata(&owners(), 2, &FALLBACK_HANDLER.parse().unwrap());
+        SafeMiner::new(
+            &FACTORY.parse().unwrap(),
+            &SINGLETON.parse().unwrap(),
+            &hex::decode(PROXY_CREATION_CODE).unwrap(),
+            &initializer,
+            salt_nonce,
+            batch_size,
+        )
+    }
+
+    #[test]
+    fn setup_calldata_is_abi_encoded() {
+        let calldata = setup_calldata(&owners(), 2, &FALLBACK_HANDLER.parse().unwrap());
+        assert_eq!(
+            hex::encode(calldata),
+            concat!(
+                "b63e800d",
+                "0000000000000000000000000000000000000000000000000000000000000100", // owners offset
+                "0000000000000000000000000000000000000000000000000000000000000002", // threshold
+                "0000000000000000000000000000000000000000000000000000000000000000", // to
+                "0000000000000000000000000000000000000000000000000000000000000160", // data offset
+                "000000000000000000000000f48f2b2d2a534e402487b3ee7c18c33aec0fe5e4", // fallbackHandler
+                "0000000000000000000000000000000000000000000000000000000000000000", // paymentToken
+                "0000000000000000000000000000000000000000000000000000000000000000", // payment
+                "0000000000000000000000000000000000000000000000000000000000000000", // paymentReceiver
+                "0000000000000000000000000000000000000000000000000000000000000002",
+                "0000000000000000000000001111111111111111111111111111111111111111",
+                "0000000000000000000000002222222222222222222222222222222222222222",
+                "0000000000000000000000000000000000000000000000000000000000000000", // data.length
+            )
+        );
+    }
+
+    #[test]
+    fn proxy_address_test_vector() {
+        let mut salt_nonce = [0u8; 32];
+        salt_nonce[24..].copy_from_slice(&0x0123456789abcdef_u64.to_be_bytes());
+        let mut miner = miner(salt_nonce, 1);
+        assert!(miner.next_batch());
+        assert_eq!(miner.addresses()[0], "408c9d0bb7cf5d15d46b2aab7b6a8456bb928f5c".parse().unwrap());
+        assert_eq!(miner.salt_nonce(0), salt_nonce);
+    }
+
+    /// `createProxyWithNonce`'s CREATE2 address, spelled out hash by hash.
+    fn proxy_address(salt_nonce: [u8; 32]) -> H160 {
+        let initializer = setup_calldata(&owners(), 2, &FALLBACK_HANDLER.parse().unwrap());
+        let salt = Keccak256::new().chain_update(Keccak256::digest(initializer)).chain_update(salt_nonce).finalize();
+        let mut deployment_data = hex::decode(PROXY_CREATION_CODE).unwrap();
+        deployment_data.extend(address_word(&SINGLETON.parse().unwrap()));
+        let hash = Keccak256::new()
+            .chain_update([0xff])
+            .chain_update(hex::decode(FACTORY).unwrap())
+            .chain_update(salt)
+            .chain_update(Keccak256::digest(deployment_data))
+            .finalize();
+        H160::from_slice(&hash[12..])
+    }
+
+    /// Every address of a few batches against the salt nonce reported for it,
+    /// starting just below a 2^64 boundary of the counter.
+    #[test]
+    fn salt_nonce_reproduces_addresses() {
+        let mut salt_nonce = [0xab; 32];
+        salt_nonce[24..].copy_from_slice(&(u64::MAX - 5).to_be_bytes());
+        let mut miner = miner(salt_nonce, 4);
+
+        for _ in 0..3 {
+            assert!(miner.next_batch());
+            for (i, address) in miner.addresses().iter().enumerate() {
+                assert_eq!(*address, proxy_address(miner.salt_nonce(i)), "address {} of the batch", i);
+            }
+        }
+    }
+}
@@ -0,0 +1,208 @@
+use crate::matcher::{Candidate, Matcher};
+use clap::ValueEnum;
+use ethereum_types::H160;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// How addresses are ranked in the open-ended optimization mode.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Scoring {
+    /// Longest run of a single repeated hex character
+    LongestRun,
+    /// Number of zero hex characters anywhere
+    ZeroNibbles,
+    /// Number of leading hex characters shared with `--target`
+    TargetPrefix,
+}
+
+impl std::fmt::Display for Scoring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+#[derive(Clone)]
+pub struct Scorer {
+    scoring: Scoring,
+    /// Nibbles of the target for [`Scoring::TargetPrefix`].
+    target: Vec<u8>,
+}
+
+impl Scorer {
+    pub fn new(scoring: Scoring, target: Option<&str>) -> Result<Self, String> {
+        let target = match (scoring, target) {
+            (Scoring::TargetPrefix, None) => {
+                return Err("--score target-prefix needs a --target".to_string());
+            }
+            (_, Some(target)) => {
+                let hex = target.strip_prefix("0x").unwrap_or(target);
+                if hex.len() > 40 {
+                    return Err("target is longer than 40 hex characters".to_string());
+                }
+                hex.chars()
+                    .map(|c| {
+                        c.to_digit(16)
+                            .map(|d| d as u8)
+                            .ok_or_else(|| format!("target contains non-hex character '{}'", c))
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            (_, None) => Vec::new(),
+        };
+
+        Ok(Scorer { scoring, target })
+    }
+
+    pub fn score(&self, address: &H160) -> u32 {
+        let nibbles = address
+            .as_bytes()
+            .iter()
+            .flat_map(|&byte| [byte >> 4, byte & 0xf]);
+
+        match self.scoring {
+            Scoring::LongestRun => {
+                let (mut longest, mut run, mut previous) = (0, 0, None);
+                for nibble in nibbles {
+                    run = if previous == Some(nibble) { run + 1 } else { 1 };
+                    longest = longest.max(run);
+                    previous = Some(nibble);
+                }
+                longest
+            }
+            Scoring::ZeroNibbles => nibbles.filter(|&nibble| nibble == 0).count() as u32,
+            Scoring::TargetPrefix => nibbles
+                .zip(&self.target)
+                .take_while(|(nibble, target)| nibble == *target)
+                .count() as u32,
+        }
+    }
+}
+
+/// Accepts every address, scored.
+impl Matcher for Scorer {
+    fn evaluate(&self, candidate: &Candidate) -> Option<u32> {
+        Some(self.score(candidate.address()))
+    }
+
+    fn probability(&self) -> Option<f64> {
+        Some(1.0)
+    }
+}
+
+/// The `size` best-scoring entries seen so far, shared between workers.
+///
+/// The score needed to get onto the board is mirrored in an atomic, so
+/// workers only take the lock for candidates that will actually be inserted.
+pub struct Leaderboard<T> {
+    size: usize,
+    entries: Mutex<Vec<(u32, T)>>,
+    /// Lowest score that gets onto the board: 0 until it is full, then one
+    /// more than its last entry's. Out of reach of any `u32` score for a board
+    /// of size 0.
+    threshold: AtomicU64,
+}
+
+impl<T> Leaderboard<T> {
+    /// A board of size 0 refuses every entry.
+    pub fn new(size: usize) -> Self {
+        Leaderboard {
+            size,
+            entries: Mutex::new(Vec::with_capacity(size + 1)),
+            threshold: AtomicU64::new(if size == 0 { u64::MAX } else { 0 }),
+        }
+    }
+
+    /// Whether an entry with this score would make it onto the board.
+    pub fn qualifies(&self, score: u32) -> bool {
+        score as u64 >= self.threshold.load(Ordering::Relaxed)
+    }
+
+    pub fn insert(&self, score: u32, entry: T) {
+        if self.size == 0 {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= self.size && score <= entries[entries.len() - 1].0 {
+            return;
+        }
+
+        // Keep the board sorted best first; equal scores keep their arrival order
+        let position = entries.partition_point(|(existing, _)| *existing >= score);
+        entries.insert(position, (score, entry));
+        entries.truncate(self.size);
+
+        if entries.len() >= self.size {
+            self.threshold.store(entries[entries.len() - 1].0 as u64 + 1, Ordering::Relaxed);
+        }
+    }
+
+    /// Removes and returns all entries, best first. The returned buffer is
+    /// the one locked by [`Leaderboard::lock_memory`].
+    pub fn take(&self) -> Vec<(u32, T)> {
+        std::mem::take(&mut *self.entries.lock().unwrap())
+    }
+
+    /// Locks the entries into RAM; the board never outgrows its buffer.
+    pub fn lock_memory(&self) -> Result<(), String> {
+        crate::secure::lock_memory(&self.entries.lock().unwrap())
+    }
+
+    /// Score of the best entry and what `view` reads from it, without
+    /// copying the entry out of its locked buffer.
+    pub fn best<R>(&self, view: impl FnOnce(&T) -> R) -> Option<(u32, R)> {
+        self.entries.lock().unwrap().first().map(|(score, entry)| (*score, view(entry)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scores<T>(board: &Leaderboard<T>) -> Vec<u32> {
+        board.entries.lock().unwrap().iter().map(|(score, _)| *score).collect()
+    }
+
+    #[test]
+    fn empty_board_refuses_everything() {
+        let board = Leaderboard::new(0);
+        assert!(!board.qualifies(u32::MAX));
+        board.insert(u32::MAX, "a");
+        assert!(board.take().is_empty());
+        assert!(board.best(|entry| *entry).is_none());
+    }
+
+    #[test]
+    fn score_zero_gets_onto_a_board_that_isnt_full() {
+        let board = Leaderboard::new(2);
+        assert!(board.qualifies(0));
+        board.insert(0, "a");
+        assert!(board.qualifies(0));
+        board.insert(0, "b");
+        assert_eq!(board.best(|entry| *entry), Some((0, "a")));
+        assert!(!board.qualifies(0));
+        assert!(board.qualifies(1));
+    }
+
+    #[test]
+    fn ties_at_the_cutoff_keep_the_earlier_entry() {
+        let board = Leaderboard::new(3);
+        for (score, entry) in [(5, "a"), (3, "b"), (7, "c")] {
+            board.insert(score, entry);
+        }
+        assert_eq!(scores(&board), [7, 5, 3]);
+        assert!(!board.qualifies(3));
+        assert!(board.qualifies(4));
+
+        // A tie with the last entry doesn't displace it, even when inserted anyway
+        board.insert(3, "d");
+        assert_eq!(board.entries.lock().unwrap()[2], (3, "b"));
+
+        // A tie higher up goes after the entries it ties with
+        board.insert(5, "e");
+        assert_eq!(
+            board.take().into_iter().map(|(_, entry)| entry).collect::<Vec<_>>(),
+            ["c", "a", "e"]
+        );
+        assert!(!board.qualifies(5));
+    }
+}
@@ -0,0 +1,285 @@
+use ethereum_types::H160;
+use k256::{FieldBytes, FieldElement};
+use rand::rngs::OsRng;
+use secp256k1::{PublicKey, Scalar, Secp256k1, SecretKey};
+use sha3::{Digest, Keccak256};
+use zeroize::Zeroize;
+
+/// What a hit has to keep so that its address can be reproduced.
+#[derive(Clone)]
+pub enum Secret {
+    /// Private key of an externally owned account.
+    PrivateKey(SecretKey),
+    /// Salt of a CREATE2 deployment.
+    Salt([u8; 32]),
+    /// `saltNonce` of a Safe proxy deployment, as a big-endian uint256.
+    SaltNonce([u8; 32]),
+    /// Private key of an account whose CREATE deployment at `nonce` is the hit.
+    Deployer {
+        private_key: SecretKey,
+        address: H160,
+        nonce: u64,
+    },
+    /// BIP-39 mnemonic whose account at the searched derivation path is the hit.
+    Mnemonic {
+        phrase: String,
+        private_key: SecretKey,
+    },
+    /// Partial private key of a split-key search, to be combined with the
+    /// requester's own key.
+    PartialKey(SecretKey),
+    /// Index of the child of an existing extended public key; nothing secret.
+    ChildIndex(u32),
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        match self {
+            Secret::PrivateKey(private_key)
+            | Secret::Deployer { private_key, .. }
+            | Secret::PartialKey(private_key) => private_key.non_secure_erase(),
+            Secret::Mnemonic { phrase, private_key } => {
+                phrase.zeroize();
+                private_key.non_secure_erase();
+            }
+            Secret::Salt(_) | Secret::SaltNonce(_) | Secret::ChildIndex(_) => {}
+        }
+    }
+}
+
+/// A source of candidate addresses, owned by a single search thread.
+pub trait Worker {
+    /// Computes the next batch of candidates. Returns `false` if the worker
+    /// ran into a dead end and must be replaced by a fresh one. An empty
+    /// batch means the whole search space has been covered.
+    fn next_batch(&mut self) -> bool;
+
+    /// Candidates produced by the last call to [`Worker::next_batch`].
+    fn addresses(&self) -> &[H160];
+
+    /// Secret behind `addresses()[index]`.
+    fn secret(&self, index: usize) -> Secret;
+}
+
+/// Ethereum address of a public key.
+pub fn public_key_address(public_key: &PublicKey) -> H160 {
+    let public_key_hash = Keccak256::digest(&public_key.serialize_uncompressed()[1..]);
+    H160::from_slice(&public_key_hash[12..])
+}
+
+/// The generator point G.
+fn generator(secp: &Secp256k1<secp256k1::All>) -> PublicKey {
+    PublicKey::from_secret_key(secp, &SecretKey::from_slice(&Scalar::ONE.to_be_bytes()).unwrap())
+}
+
+/// A curve point in affine coordinates.
+#[derive(Clone, Copy)]
+struct Affine {
+    x: FieldElement,
+    y: FieldElement,
+}
+
+impl Affine {
+    fn from_public_key(public_key: &PublicKey) -> Self {
+        let bytes = public_key.serialize_uncompressed();
+        Affine {
+            x: FieldElement::from_bytes(FieldBytes::from_slice(&bytes[1..33])).unwrap(),
+            y: FieldElement::from_bytes(FieldBytes::from_slice(&bytes[33..65])).unwrap(),
+        }
+    }
+}
+
+/// Walks the keys `k, k+1, k+2, ...` from a random base key `k`.
+///
+/// Instead of a full scalar multiplication per key, each batch computes
+/// `P + G, P + 2G, ..., P + NG` from the current point `P` with affine point
+/// additions against a precomputed table of multiples of G. The N field
+/// inversions needed by those additions are shared using Montgomery's trick,
+/// so a whole batch costs a single inversion. The private key is only
+/// recovered (as `k + offset`) when the caller asks for it, i.e. on a hit,
+/// so rejected candidates never exist as private keys in memory.
+pub struct KeyWalker {
+    base: SecretKey,
+    /// `(i + 1)·G` for every `i` in the batch.
+    table: Vec<Affine>,
+    point: Affine,
+    /// Offset from `base` of `point`.
+    offset: u64,
+    /// Running products for the batch inversion.
+    products: Vec<FieldElement>,
+    addresses: Vec<H160>,
+}
+
+impl KeyWalker {
+    pub fn random(secp: &Secp256k1<secp256k1::All>, batch_size: usize) -> Self {
+        let base = SecretKey::new(&mut OsRng);
+        Self::new(&generator(secp), base, PublicKey::from_secret_key(secp, &base), batch_size)
+    }
+
+    /// Walks the points `offset + b·G` from a random `b`, for split-key
+    /// searches where `offset` is somebody else's public key. The keys this
+    /// walker reports are the partial keys `b`.
+    pub fn random_offset(secp: &Secp256k1<secp256k1::All>, offset: &PublicKey, batch_size: usize) -> Self {
+        loop {
+            let base = SecretKey::new(&mut OsRng);
+            if let Ok(point) = PublicKey::from_secret_key(secp, &base).combine(offset) {
+                return Self::new(&generator(secp), base, point, batch_size);
+            }
+        }
+    }
+
+    /// Walks the points `b·point` from a random `b`, for multiplicative
+    /// split-key searches where `point` is somebody else's public key. The
+    /// keys this walker reports are the partial keys `b`.
+    pub fn random_multiple(secp: &Secp256k1<secp256k1::All>, point: &PublicKey, batch_size: usize) -> Self {
+        let base = SecretKey::new(&mut OsRng);
+        let start = point.mul_tweak(secp, &Scalar::from(base)).unwrap();
+        Self::new(point, base, start, batch_size)
+    }
+
+    /// Walker that steps by `generator` from `point`, the point of key `base`.
+    fn new(generator: &PublicKey, base: SecretKey, point: PublicKey, batch_size: usize) -> Self {
+        let mut table = Vec::with_capacity(batch_size);
+        let mut multiple = *generator;
+        table.push(Affine::from_public_key(&multiple));
+        for _ in 1..batch_size {
+            multiple = multiple.combine(generator).unwrap();
+            table.push(Affine::from_public_key(&multiple));
+        }
+
+        KeyWalker {
+            base,
+            table,
+            point: Affine::from_public_key(&point),
+            offset: 0,
+            products: Vec::with_capacity(batch_size),
+            addresses: Vec::with_capacity(batch_size),
+        }
+    }
+
+    /// Private key behind `addresses()[index]`.
+    pub fn secret_key(&self, index: usize) -> SecretKey {
+        let offset = self.offset - self.table.len() as u64 + index as u64 + 1;
+        let mut tweak = [0u8; 32];
+        tweak[24..].copy_from_slice(&offset.to_be_bytes());
+        let tweak = Scalar::from_be_bytes(tweak).unwrap();
+        self.base.add_tweak(&tweak).unwrap()
+    }
+}
+
+impl Drop for KeyWalker {
+    fn drop(&mut self) {
+        self.base.non_secure_erase();
+    }
+}
+
+impl Worker for KeyWalker {
+    /// Computes the addresses of the next batch of keys. Returns `false` if
+    /// the walk ran into the point at infinity (i.e. `P = -(i + 1)·G`) and
+    /// must be restarted from a new base key.
+    fn next_batch(&mut self) -> bool {
+        let point = self.point;
+
+        // Denominators x(iG) - x(P), multiplied together for a single inversion
+        self.products.clear();
+        let mut product = FieldElement::ONE;
+        for multiple in &self.table {
+            product = product.mul(&(multiple.x + point.x.negate(1)));
+            self.products.push(product);
+        }
+
+        let inverse = product.invert();
+        if bool::from(inverse.is_none()) {
+            return false;
+        }
+        let mut inverse = inverse.unwrap();
+
+        // Walk back through the running products, recovering each individual
+        // inverse and finishing the affine addition for that point
+        self.addresses.clear();
+        self.addresses.resize(self.table.len(), H160::zero());
+        let mut last = point;
+        for i in (0..self.table.len()).rev() {
+            let multiple = &self.table[i];
+            let dx = multiple.x + point.x.negate(1);
+            let dx_inverse = if i == 0 {
+                inverse
+            } else {
+                inverse.mul(&self.products[i - 1])
+            };
+            inverse = inverse.mul(&dx);
+
+            let lambda = (multiple.y + point.y.negate(1)).mul(&dx_inverse);
+            let x = (lambda.square() + point.x.negate(1) + multiple.x.negate(1)).normalize();
+            let y = (lambda.mul(&(point.x + x.negate(1))) + point.y.negate(1)).normalize();
+
+            let mut public_key_bytes = [0u8; 64];
+            public_key_bytes[..32].copy_from_slice(&x.to_bytes());
+            public_key_bytes[32..].copy_from_slice(&y.to_bytes());
+            let public_key_hash = Keccak256::digest(public_key_bytes);
+            self.addresses[i] = H160::from_slice(&public_key_hash[12..32]);
+
+            if i == self.table.len() - 1 {
+                last = Affine { x, y };
+            }
+        }
+
+        self.point = last;
+        self.offset += self.table.len() as u64;
+        true
+    }
+
+    fn addresses(&self) -> &[H160] {
+        &self.addresses
+    }
+
+    fn secret(&self, index: usize) -> Secret {
+        Secret::PrivateKey(self.secret_key(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs a few batches and checks every address against the one derived
+    /// the slow way from the key the walker reports for it.
+    fn check_walker(mut walker: KeyWalker, expected_public_key: impl Fn(&SecretKey) -> PublicKey) {
+        for _ in 0..3 {
+            assert!(walker.next_batch());
+            for (i, address) in walker.addresses().iter().enumerate() {
+                let expected = public_key_address(&expected_public_key(&walker.secret_key(i)));
+                assert_eq!(*address, expected, "address {} of the batch", i);
+            }
+        }
+    }
+
+    #[test]
+    fn key_walker_matches_secp256k1() {
+        let secp = Secp256k1::new();
+        for batch_size in [1, 2, 7, 64] {
+            let walker = KeyWalker::random(&secp, batch_size);
+            check_walker(walker, |key| PublicKey::from_secret_key(&secp, key));
+        }
+    }
+
+    #[test]
+    fn offset_walker_matches_secp256k1() {
+        let secp = Secp256k1::new();
+        let offset = PublicKey::from_secret_key(&secp, &SecretKey::new(&mut OsRng));
+        for batch_size in [1, 7, 64] {
+            let walker = KeyWalker::random_offset(&secp, &offset, batch_size);
+            check_walker(walker, |key| PublicKey::from_secret_key(&secp, key).combine(&offset).unwrap());
+        }
+    }
+
+    #[test]
+    fn multiple_walker_matches_secp256k1() {
+        let secp = Secp256k1::new();
+        let point = PublicKey::from_secret_key(&secp, &SecretKey::new(&mut OsRng));
+        for batch_size in [1, 7, 64] {
+            let walker = KeyWalker::random_multiple(&secp, &point, batch_size);
+            check_walker(walker, |key| point.mul_tweak(&secp, &Scalar::from(*key)).unwrap());
+        }
+    }
+}
@@ -0,0 +1,485 @@
+use crate::matcher::{Candidate, Matcher};
+use crate::score::Leaderboard;
+use crate::search::{KeyWalker, Secret, Worker};
+use crate::secure;
+use ethereum_types::H160;
+use rayon::prelude::*;
+use secp256k1::Secp256k1;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// A matching address together with the secret that reproduces it.
+#[derive(Clone)]
+pub struct Hit {
+    pub secret: Secret,
+    pub address: H160,
+}
+
+/// How a search runs, independent of what it searches and for what.
+#[derive(Clone)]
+pub struct SearchConfig {
+    /// Number of worker threads.
+    pub threads: usize,
+    /// Number of hits after which the search stops. Ignored with `top`.
+    pub quantity: usize,
+    /// Keep the `top` matches with the best [`Matcher::evaluate`] scores
+    /// instead of stopping at `quantity` hits. Such a search only ends on
+    /// its budget or when cancelled.
+    pub top: Option<usize>,
+    /// Time budget.
+    pub duration: Option<Duration>,
+    /// Budget of candidates to try.
+    pub max_attempts: Option<u64>,
+    /// Candidates per batch of [`Searcher::start_keys`].
+    pub batch_size: usize,
+}
+
+impl SearchConfig {
+    /// Checks that a search with this config can run and end: at least one
+    /// thread and one candidate per batch, and something to stop at.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.threads == 0 {
+            return Err("a search needs at least one thread".to_string());
+        }
+        if self.batch_size == 0 {
+            return Err("batch size must be at least 1".to_string());
+        }
+        match self.top {
+            Some(0) => Err("leaderboard size must be at least 1".to_string()),
+            None if self.quantity == 0 => Err("quantity must be at least 1".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            threads: num_cpus::get(),
+            quantity: 1,
+            top: None,
+            duration: None,
+            max_attempts: None,
+            batch_size: 1024,
+        }
+    }
+}
+
+/// A snapshot of a running search.
+#[derive(Clone, Debug)]
+pub struct Stats {
+    pub attempts: u64,
+    pub elapsed: Duration,
+    /// Hits so far, not counting leaderboard entries.
+    pub found: usize,
+    /// Best score and address on the leaderboard, when scoring.
+    pub best: Option<(u32, H160)>,
+}
+
+impl Stats {
+    /// Candidates per second.
+    pub fn rate(&self) -> f64 {
+        let elapsed = self.elapsed.as_secs_f64();
+        if elapsed > 0.0 {
+            self.attempts as f64 / elapsed
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Stops a search from any thread. Workers finish their current batch first.
+#[derive(Clone, Default)]
+pub struct CancelHandle(Arc<AtomicBool>);
+
+impl CancelHandle {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether the search was cancelled or has run its course.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Outcome of a finished search.
+pub struct SearchResults {
+    /// Hits that weren't already taken from the [`Search`] iterator, in the
+    /// buffer they were locked into RAM in.
+    pub found: Vec<Hit>,
+    /// Leaderboard when scoring, best first.
+    pub leaderboard: Vec<(u32, Hit)>,
+    pub attempts: u64,
+    pub elapsed: Duration,
+    /// Why found keys could not be locked into RAM, if they couldn't.
+    pub lock_error: Option<String>,
+}
+
+type StatsCallback = Box<dyn Fn(&Stats) + Send>;
+
+/// Runs a search for addresses accepted by a [`Matcher`] on a pool of
+/// threads, each drawing candidates from its own [`Worker`].
+pub struct Searcher {
+    config: SearchConfig,
+    matcher: Box<dyn Matcher>,
+    stats_callback: Option<(Duration, StatsCallback)>,
+    cancel: CancelHandle,
+}
+
+impl Searcher {
+    /// Fails on the configs [`SearchConfig::validate`] rejects.
+    pub fn new(config: SearchConfig, matcher: impl Matcher + 'static) -> Result<Self, String> {
+        config.validate()?;
+        Ok(Searcher {
+            config,
+            matcher: Box::new(matcher),
+            stats_callback: None,
+            cancel: CancelHandle::default(),
+        })
+    }
+
+    /// Calls `callback` from a separate thread every `interval` while the
+    /// search runs.
+    pub fn on_stats(mut self, interval: Duration, callback: impl Fn(&Stats) + Send + 'static) -> Self {
+        self.stats_callback = Some((interval, Box::new(callback)));
+        self
+    }
+
+    /// Handle that cancels the search once started.
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    /// Searches private keys.
+    pub fn start_keys(self) -> Search {
+        let batch_size = self.config.batch_size;
+        self.start(move || KeyWalker::random(&Secp256k1::new(), batch_size))
+    }
+
+    /// Starts searching in the background, with one worker made by
+    /// `new_worker` per thread (and more if a worker runs into a dead end).
+    pub fn start<W: Worker>(self, new_worker: impl Fn() -> W + Send + Sync + 'static) -> Search {
+        let Searcher {
+            config,
+            matcher,
+            stats_callback,
+            cancel,
+        } = self;
+
+        // Hits only ever live in these two buffers, which are sized up front
+        // and locked before the first one comes in (a mnemonic phrase keeps
+        // its text on the heap, but is still wiped on drop)
+        let capacity = if config.top.is_some() { 0 } else { config.quantity };
+        let state = Arc::new(State {
+            attempts: AtomicU64::new(0),
+            found: AtomicUsize::new(0),
+            hits: Mutex::new(Hits {
+                pending: Vec::with_capacity(capacity),
+                done: false,
+            }),
+            hit_added: Condvar::new(),
+            leaderboard: Leaderboard::new(config.top.unwrap_or(0)),
+            start_time: Instant::now(),
+            lock_error: Mutex::new(None),
+        });
+        let locked = secure::lock_memory(&state.hits.lock().unwrap().pending).and_then(|_| state.leaderboard.lock_memory());
+        if let Err(err) = locked {
+            *state.lock_error.lock().unwrap() = Some(err);
+        }
+
+        let stats_thread = stats_callback.map(|(interval, callback)| {
+            let state = state.clone();
+            let cancel = cancel.clone();
+            let quantity = config.quantity;
+            std::thread::spawn(move || loop {
+                std::thread::sleep(interval);
+                if cancel.is_cancelled() {
+                    break;
+                }
+                callback(&state.stats(quantity));
+            })
+        });
+
+        let quantity = config.quantity;
+        let search_thread = {
+            let state = state.clone();
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                let _done = MarkDone(&state);
+                let pool = rayon::ThreadPoolBuilder::new().num_threads(config.threads).build().unwrap();
+                pool.install(|| {
+                    (0..config.threads).into_par_iter().for_each(|_| {
+                        run_worker(&config, &*matcher, &state, &cancel, &new_worker);
+                    });
+                });
+
+                // Also stops the stats thread
+                cancel.cancel();
+            })
+        };
+
+        Search {
+            quantity,
+            state,
+            cancel,
+            threads: vec![search_thread].into_iter().chain(stats_thread).collect(),
+        }
+    }
+}
+
+/// Counters shared between the workers, the stats thread and the [`Search`].
+struct State {
+    attempts: AtomicU64,
+    /// Hits claimed so far; may run past `quantity` by the hits that were dropped.
+    found: AtomicUsize,
+    hits: Mutex<Hits>,
+    hit_added: Condvar,
+    leaderboard: Leaderboard<Hit>,
+    start_time: Instant,
+    lock_error: Mutex<Option<String>>,
+}
+
+/// Hits waiting to be taken from the [`Search`].
+struct Hits {
+    /// Never grows past the capacity it was locked with.
+    pending: Vec<Hit>,
+    /// Set once the workers have stopped.
+    done: bool,
+}
+
+/// Wakes up the [`Search`] iterator once the workers have stopped, even if
+/// one of them panicked.
+struct MarkDone<'a>(&'a State);
+
+impl Drop for MarkDone<'_> {
+    fn drop(&mut self) {
+        self.0.hits.lock().unwrap_or_else(PoisonError::into_inner).done = true;
+        self.0.hit_added.notify_all();
+    }
+}
+
+impl State {
+    fn stats(&self, quantity: usize) -> Stats {
+        Stats {
+            attempts: self.attempts.load(Ordering::Relaxed),
+            elapsed: self.start_time.elapsed(),
+            found: self.found.load(Ordering::Relaxed).min(quantity),
+            best: self.leaderboard.best(|hit| hit.address),
+        }
+    }
+}
+
+/// One search thread: draws batches from its worker until the search stops.
+fn run_worker<W: Worker>(
+    config: &SearchConfig,
+    matcher: &dyn Matcher,
+    state: &State,
+    cancel: &CancelHandle,
+    new_worker: &impl Fn() -> W,
+) {
+    let mut worker = new_worker();
+
+    loop {
+        // Check if we're done
+        if cancel.is_cancelled() {
+            break;
+        }
+
+        // Stop everyone once the time or attempt budget is spent
+        let out_of_time = config.duration.is_some_and(|duration| state.start_time.elapsed() >= duration);
+        let out_of_attempts = config.max_attempts.is_some_and(|max| state.attempts.load(Ordering::Relaxed) >= max);
+        if out_of_time || out_of_attempts {
+            cancel.cancel();
+            break;
+        }
+
+        // Start over with a fresh worker if this one ran into a dead end
+        if !worker.next_batch() {
+            worker = new_worker();
+            continue;
+        }
+
+        // Stop everyone once there is nothing left to search
+        if worker.addresses().is_empty() {
+            cancel.cancel();
+            break;
+        }
+        state.attempts.fetch_add(worker.addresses().len() as u64, Ordering::Relaxed);
+
+        for (i, address) in worker.addresses().iter().enumerate() {
+            let Some(score) = matcher.evaluate(&Candidate::new(address)) else {
+                continue;
+            };
+
+            if config.top.is_some() {
+                if state.leaderboard.qualifies(score) {
+                    state.leaderboard.insert(score, Hit {
+                        secret: worker.secret(i),
+                        address: *address,
+                    });
+                }
+            } else {
+                // Only send if we haven't reached the quantity
+                let found = state.found.fetch_add(1, Ordering::Relaxed);
+                if found < config.quantity {
+                    state.hits.lock().unwrap().pending.push(Hit {
+                        secret: worker.secret(i),
+                        address: *address,
+                    });
+                    state.hit_added.notify_all();
+
+                    // If we've found all the addresses, mark as completed
+                    if found + 1 >= config.quantity {
+                        cancel.cancel();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A running search. Iterating yields hits as they are found, until the
+/// search ends; [`Search::finish`] waits for the end and collects the rest.
+///
+/// Hits wait in memory locked into RAM until they are taken. Those taken
+/// through the iterator are the caller's to protect, so prefer `finish` when
+/// they must not be swapped to disk.
+///
+/// Dropping a search cancels it and waits for its threads.
+pub struct Search {
+    quantity: usize,
+    state: Arc<State>,
+    cancel: CancelHandle,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Search {
+    pub fn cancel_handle(&self) -> CancelHandle {
+        self.cancel.clone()
+    }
+
+    pub fn stats(&self) -> Stats {
+        self.state.stats(self.quantity)
+    }
+
+    /// Waits for the search to end and returns the hits not yet taken from
+    /// the iterator, the leaderboard and the final counters.
+    pub fn finish(mut self) -> SearchResults {
+        self.join();
+
+        SearchResults {
+            found: std::mem::take(&mut self.state.hits.lock().unwrap().pending),
+            leaderboard: self.state.leaderboard.take(),
+            attempts: self.state.attempts.load(Ordering::Relaxed),
+            elapsed: self.state.start_time.elapsed(),
+            lock_error: self.state.lock_error.lock().unwrap().take(),
+        }
+    }
+
+    fn join(&mut self) {
+        for thread in self.threads.drain(..) {
+            if let Err(panic) = thread.join() {
+                std::panic::resume_unwind(panic);
+            }
+        }
+    }
+}
+
+impl Iterator for Search {
+    type Item = Hit;
+
+    fn next(&mut self) -> Option<Hit> {
+        let mut hits = self.state.hits.lock().unwrap();
+        loop {
+            if !hits.pending.is_empty() {
+                return Some(hits.pending.remove(0));
+            }
+            if hits.done {
+                return None;
+            }
+            hits = self.state.hit_added.wait(hits).unwrap();
+        }
+    }
+}
+
+impl Drop for Search {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+        self.join();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::matcher::Predicate;
+
+    fn config(threads: usize, quantity: usize) -> SearchConfig {
+        SearchConfig {
+            threads,
+            quantity,
+            batch_size: 16,
+            ..SearchConfig::default()
+        }
+    }
+
+    fn everything() -> Predicate<fn(&Candidate) -> bool> {
+        Predicate(|_| true)
+    }
+
+    fn nothing() -> Predicate<fn(&Candidate) -> bool> {
+        Predicate(|_| false)
+    }
+
+    #[test]
+    fn rejects_configs_that_cannot_run_or_end() {
+        assert!(Searcher::new(config(0, 1), everything()).is_err());
+        assert!(Searcher::new(config(1, 0), everything()).is_err());
+        assert!(Searcher::new(SearchConfig { batch_size: 0, ..config(1, 1) }, everything()).is_err());
+        assert!(Searcher::new(SearchConfig { top: Some(0), ..config(1, 1) }, everything()).is_err());
+        assert!(Searcher::new(SearchConfig { top: Some(1), ..config(1, 0) }, everything()).is_ok());
+    }
+
+    #[test]
+    fn finish_returns_exactly_quantity_hits() {
+        // Every thread's first batch alone overshoots the quantity
+        for quantity in [1, 5, 40] {
+            let results = Searcher::new(config(4, quantity), everything()).unwrap().start_keys().finish();
+            assert_eq!(results.found.len(), quantity);
+            assert!(results.attempts >= quantity as u64);
+        }
+    }
+
+    #[test]
+    fn iterated_hits_are_not_returned_again() {
+        let mut search = Searcher::new(config(2, 5), everything()).unwrap().start_keys();
+        let first: Vec<Hit> = search.by_ref().take(2).collect();
+        let results = search.finish();
+        assert_eq!(first.len(), 2);
+        assert_eq!(results.found.len(), 3);
+        assert!(results.found.iter().all(|hit| first.iter().all(|taken| taken.address != hit.address)));
+    }
+
+    #[test]
+    fn iteration_ends_after_cancel() {
+        let search = Searcher::new(config(2, 1), nothing()).unwrap().start_keys();
+        let cancel = search.cancel_handle();
+        let canceller = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            cancel.cancel();
+        });
+        assert_eq!(search.count(), 0);
+        canceller.join().unwrap();
+    }
+
+    #[test]
+    fn attempt_budget_ends_the_search() {
+        let config = SearchConfig { max_attempts: Some(100), ..config(2, 1) };
+        let results = Searcher::new(config, nothing()).unwrap().start_keys().finish();
+        assert!(results.found.is_empty());
+        assert!(results.attempts >= 100);
+    }
+}
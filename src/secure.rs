@@ -0,0 +1,36 @@
+/// Disables core dumps for the rest of the process, so a crash can't write
+/// keys to disk. On Linux the process is also made non-dumpable, which keeps
+/// other processes of the same user from attaching to it and reading memory.
+pub fn disable_core_dumps() -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        let limit = libc::rlimit {
+            rlim_cur: 0,
+            rlim_max: 0,
+        };
+        if unsafe { libc::setrlimit(libc::RLIMIT_CORE, &limit) } != 0 {
+            return Err(format!("cannot disable core dumps: {}", std::io::Error::last_os_error()));
+        }
+        #[cfg(target_os = "linux")]
+        if unsafe { libc::prctl(libc::PR_SET_DUMPABLE, 0, 0, 0, 0) } != 0 {
+            return Err(format!("cannot disable core dumps: {}", std::io::Error::last_os_error()));
+        }
+    }
+    Ok(())
+}
+
+/// Locks the pages behind the whole capacity of `buffer` into RAM, so the
+/// entries it will hold are never swapped out. The buffer must not grow
+/// beyond its capacity afterwards, or the entries move to unlocked memory.
+pub fn lock_memory<T>(buffer: &Vec<T>) -> Result<(), String> {
+    #[cfg(unix)]
+    {
+        let length = buffer.capacity() * std::mem::size_of::<T>();
+        if length > 0 && unsafe { libc::mlock(buffer.as_ptr().cast(), length) } != 0 {
+            return Err(format!("cannot lock memory: {}", std::io::Error::last_os_error()));
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = buffer;
+    Ok(())
+}
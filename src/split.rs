@@ -0,0 +1,61 @@
+use crate::search::{KeyWalker, Secret, Worker};
+use clap::ValueEnum;
+use ethereum_types::H160;
+use secp256k1::{Scalar, SecretKey};
+
+/// How the requester's key `a` and the partial key `b` make up the final key.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum SplitScheme {
+    /// Final key `a + b`, searched as `A + b·G`
+    Additive,
+    /// Final key `a·b`, searched as `b·A`
+    Multiplicative,
+}
+
+impl std::fmt::Display for SplitScheme {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// Searches for a partial key `b` such that `A + b·G` (or `b·A`) has a vanity
+/// address, where `A` is the requester's public key.
+///
+/// Only public data is involved, so the search can run on untrusted
+/// machines: the final key is only known to whoever holds `a`.
+pub struct SplitKeyMiner {
+    walker: KeyWalker,
+}
+
+impl SplitKeyMiner {
+    /// `walker` must come from [`KeyWalker::random_offset`] (additive) or
+    /// [`KeyWalker::random_multiple`] (multiplicative) with the requester's
+    /// public key.
+    pub fn new(walker: KeyWalker) -> Self {
+        SplitKeyMiner { walker }
+    }
+}
+
+impl Worker for SplitKeyMiner {
+    fn next_batch(&mut self) -> bool {
+        self.walker.next_batch()
+    }
+
+    fn addresses(&self) -> &[H160] {
+        self.walker.addresses()
+    }
+
+    fn secret(&self, index: usize) -> Secret {
+        Secret::PartialKey(self.walker.secret_key(index))
+    }
+}
+
+/// The final key of a split-key search. Returns `None` in the negligible
+/// case that an additive split sums to zero.
+pub fn combine(scheme: SplitScheme, private_key: &SecretKey, partial_key: &SecretKey) -> Option<SecretKey> {
+    let partial_key = Scalar::from(*partial_key);
+    match scheme {
+        SplitScheme::Additive => private_key.add_tweak(&partial_key).ok(),
+        SplitScheme::Multiplicative => private_key.mul_tweak(&partial_key).ok(),
+    }
+}